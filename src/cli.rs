@@ -5,14 +5,23 @@ use cargo::{
 	GlobalContext,
 };
 use cargo_credential::Secret;
+use clap::Parser;
 use regex::Regex;
 use semver::Version;
-use std::{fs, path::PathBuf, str::FromStr};
-use toml_edit::Value;
+use std::{
+	fs,
+	path::{Path, PathBuf},
+	str::FromStr,
+};
 
 use crate::{
-	commands::{self, IndependenceCtx},
-	util::{handle_empty_package_is_failures, make_pkg_predicate, members_deep},
+	commands::{self, IndependenceCtx, LintPolicy},
+	config::{self, ProfileConfig, RequirementStyle, WorkspaceConfig},
+	editor::ManifestEditor,
+	util::{
+		handle_empty_package_is_failures, make_pkg_predicate, make_pkg_selector, members_deep,
+		ReportFormat,
+	},
 };
 
 fn parse_regex(src: &str) -> Result<Regex, anyhow::Error> {
@@ -31,7 +40,10 @@ fn parse_compile_mode_str(src: &str) -> anyhow::Result<CompileMode> {
 	})
 }
 
-#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(
+	clap::ValueEnum, serde::Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq, Eq,
+)]
+#[serde(rename_all = "kebab-case")]
 pub enum GenerateReadmeMode {
 	// Generate Readme only if it is missing.
 	IfMissing,
@@ -77,13 +89,62 @@ pub struct PackageSelectOptions {
 	/// Compares the current git `head` to the reference given, identifies which files changed
 	/// and attempts to identify the packages and its dependents through that mechanism. You
 	/// can use any `tag`, `branch` or `commit`, but you must be sure it is available
-	/// (and up to date) locally.
+	/// (and up to date) locally. Which files count as a change can be configured through
+	/// `[workspace.metadata.dragons.changes]` and `[package.metadata.dragons.changes]`.
 	#[clap(short = 'c', long = "changed-since")]
 	pub changed_since: Option<String>,
 
 	/// Even if not selected by default, also include depedencies with a pre (cascading)
 	#[clap(long)]
 	pub include_pre_deps: bool,
+
+	/// Select the packages, which changed compared to the published version.
+	///
+	/// Packages each crate and compares the normalized content with the `.crate` published
+	/// under the same version, as found in the local cargo registry cache (or any of the
+	/// `--registry-cache` directories). Selects the ones that differ, as they need a version bump
+	/// before they can be released. Packages without a published `.crate` found are ignored.
+	#[clap(long)]
+	pub changed_vs_registry: bool,
+
+	/// Additional directory to look for published `.crate` files in
+	///
+	/// Used as a stand-in or in addition to the local cargo registry cache by
+	/// `--changed-vs-registry` and `status`. Expects files named `<name>-<version>.crate`.
+	#[clap(long, value_hint = clap::ValueHint::DirPath)]
+	pub registry_cache: Vec<PathBuf>,
+
+	/// Explain why each package was selected or skipped
+	///
+	/// Prints every package of the workspace together with the reason deciding whether it was
+	/// selected, either as a table or, with `--explain=json`, as JSON.
+	#[clap(long, value_enum, num_args = 0..=1, require_equals = true, default_missing_value = "table")]
+	pub explain: Option<ReportFormat>,
+}
+
+impl PackageSelectOptions {
+	/// Fill in the defaults from the workspace configuration, where nothing was given on the
+	/// command line.
+	///
+	/// `skip` and `ignore-pre-version` defaults are not applied if an explicit selection
+	/// (`--packages`, `--changed-since`, `--changed-vs-registry`) was made.
+	pub fn apply_config(&mut self, config: &WorkspaceConfig) -> Result<(), anyhow::Error> {
+		if !self.packages.is_empty() || self.changed_since.is_some() || self.changed_vs_registry {
+			return Ok(());
+		}
+		if self.skip.is_empty() {
+			self.skip = config
+				.skip
+				.iter()
+				.map(|s| parse_regex(s))
+				.collect::<Result<_, _>>()
+				.context("Invalid `skip` in `[workspace.metadata.dragons]`")?;
+		}
+		if self.ignore_pre_version.is_empty() {
+			config.ignore_pre_version.clone_into(&mut self.ignore_pre_version);
+		}
+		Ok(())
+	}
 }
 
 #[derive(clap::Subcommand, Debug)]
@@ -108,6 +169,16 @@ pub enum VersionCommand {
 		/// Hard set to the new version, do not check whether the given one still matches
 		#[arg(long)]
 		force_update: bool,
+		/// Cascade the bump to dependents
+		///
+		/// Dependents re-exporting a crate bumped incompatibly get a breaking bump themselves,
+		/// propagating further, any other dependent gets a patch bump. The plan is shown before
+		/// any manifest is written and needs to be confirmed.
+		#[arg(long)]
+		cascade: bool,
+		/// Apply the cascaded plan without asking for confirmation
+		#[arg(short, long, requires = "cascade")]
+		yes: bool,
 	},
 	/// Smart bumping of crates for the next breaking release and add a `-dev`-pre-release-tag
 	BumpToDev {
@@ -118,6 +189,16 @@ pub enum VersionCommand {
 		/// Hard set to the new version, do not check whether the given one still matches
 		#[arg(long)]
 		force_update: bool,
+		/// Cascade the bump to dependents
+		///
+		/// Dependents re-exporting a crate bumped incompatibly get a breaking bump themselves,
+		/// propagating further, any other dependent gets a patch bump. The plan is shown before
+		/// any manifest is written and needs to be confirmed.
+		#[arg(long)]
+		cascade: bool,
+		/// Apply the cascaded plan without asking for confirmation
+		#[arg(short, long, requires = "cascade")]
+		yes: bool,
 		/// Use this identifier instead of `dev`  for the pre-release
 		#[arg(long)]
 		pre_tag: Option<String>,
@@ -131,6 +212,45 @@ pub enum VersionCommand {
 		/// Hard set to the new version, do not check whether the given one still matches
 		#[arg(long)]
 		force_update: bool,
+		/// Cascade the bump to dependents
+		///
+		/// Dependents re-exporting a crate bumped incompatibly get a breaking bump themselves,
+		/// propagating further, any other dependent gets a patch bump. The plan is shown before
+		/// any manifest is written and needs to be confirmed.
+		#[arg(long)]
+		cascade: bool,
+		/// Apply the cascaded plan without asking for confirmation
+		#[arg(short, long, requires = "cascade")]
+		yes: bool,
+		/// Pick the next pre-release number not published yet
+		///
+		/// Queries the index for the pre-releases of the version published already, so re-running
+		/// after a failed publish doesn't collide with them.
+		#[arg(long)]
+		from_registry: bool,
+	},
+	/// Move pre-releases to a later channel, e.g. from `alpha.3` to `beta.1`, or to the release
+	///
+	/// The channels are ordered as configured in `pre-release-channels`, by default `alpha`,
+	/// `beta` and `rc`. Pre-releases not on any channel, like `dev`, can be promoted to any of
+	/// them. Released versions and those on the target channel already are left as they are.
+	Promote {
+		#[command(flatten)]
+		pkg_opts: PackageSelectOptions,
+		/// The channel to promote to, or `release`
+		#[arg(long)]
+		to: String,
+		/// Force an update of dependencies
+		///
+		/// Hard set to the new version, do not check whether the given one still matches
+		#[arg(long)]
+		force_update: bool,
+		/// Pick the next pre-release number not published yet
+		///
+		/// Queries the index for the pre-releases of the version published already, so re-running
+		/// after a failed publish doesn't collide with them.
+		#[arg(long)]
+		from_registry: bool,
 	},
 	/// Increase the patch version, unset prerelease
 	BumpPatch {
@@ -141,6 +261,16 @@ pub enum VersionCommand {
 		/// Hard set to the new version, do not check whether the given one still matches
 		#[arg(long)]
 		force_update: bool,
+		/// Cascade the bump to dependents
+		///
+		/// Dependents re-exporting a crate bumped incompatibly get a breaking bump themselves,
+		/// propagating further, any other dependent gets a patch bump. The plan is shown before
+		/// any manifest is written and needs to be confirmed.
+		#[arg(long)]
+		cascade: bool,
+		/// Apply the cascaded plan without asking for confirmation
+		#[arg(short, long, requires = "cascade")]
+		yes: bool,
 	},
 	/// Increase the minor version, unset prerelease and patch
 	BumpMinor {
@@ -151,6 +281,16 @@ pub enum VersionCommand {
 		/// Hard set to the new version, do not check whether the given one still matches
 		#[arg(long)]
 		force_update: bool,
+		/// Cascade the bump to dependents
+		///
+		/// Dependents re-exporting a crate bumped incompatibly get a breaking bump themselves,
+		/// propagating further, any other dependent gets a patch bump. The plan is shown before
+		/// any manifest is written and needs to be confirmed.
+		#[arg(long)]
+		cascade: bool,
+		/// Apply the cascaded plan without asking for confirmation
+		#[arg(short, long, requires = "cascade")]
+		yes: bool,
 	},
 	/// Increase the major version, unset prerelease, minor and patch
 	BumpMajor {
@@ -161,6 +301,16 @@ pub enum VersionCommand {
 		/// Hard set to the new version, do not check whether the given one still matches
 		#[arg(long)]
 		force_update: bool,
+		/// Cascade the bump to dependents
+		///
+		/// Dependents re-exporting a crate bumped incompatibly get a breaking bump themselves,
+		/// propagating further, any other dependent gets a patch bump. The plan is shown before
+		/// any manifest is written and needs to be confirmed.
+		#[arg(long)]
+		cascade: bool,
+		/// Apply the cascaded plan without asking for confirmation
+		#[arg(short, long, requires = "cascade")]
+		yes: bool,
 	},
 	/// Hard set version to given string
 	Set {
@@ -179,6 +329,9 @@ pub enum VersionCommand {
 		#[command(flatten)]
 		pkg_opts: PackageSelectOptions,
 		/// The string to set the pre-release to
+		///
+		/// May contain the placeholders `{date}` (`YYYYMMDD`), `{git_sha}` and
+		/// `{commit_count_since_tag}`, filled in from the git repository.
 		#[arg()]
 		pre: String,
 		/// Force an update of dependencies
@@ -192,6 +345,9 @@ pub enum VersionCommand {
 		#[command(flatten)]
 		pkg_opts: PackageSelectOptions,
 		/// The specific metadata to set to
+		///
+		/// May contain the placeholders `{date}` (`YYYYMMDD`), `{git_sha}` and
+		/// `{commit_count_since_tag}`, filled in from the git repository.
 		#[arg()]
 		meta: String,
 		/// Force an update of dependencies
@@ -200,6 +356,74 @@ pub enum VersionCommand {
 		#[arg(long)]
 		force_update: bool,
 	},
+	/// Set a dated pre-release for nightly builds, e.g. `1.4.0-nightly.20261016`
+	///
+	/// Released crates are bumped for the next breaking release first, like `bump-to-dev`,
+	/// crates on a pre-release keep their version and only get the pre-release replaced.
+	Nightly {
+		#[command(flatten)]
+		pkg_opts: PackageSelectOptions,
+		/// Force an update of dependencies
+		///
+		/// Hard set to the new version, do not check whether the given one still matches
+		#[arg(long)]
+		force_update: bool,
+		/// The pre-release to set, may contain the placeholders of `set-pre`
+		#[arg(long, default_value = "nightly.{date}")]
+		pre: String,
+	},
+	/// Check the version requirements on workspace members
+	///
+	/// Reports internal requirements not matching the member's version, path dependencies
+	/// without a `version`, pre-release members required by released ones and crates required
+	/// with differing styles. Also run as part of `check`.
+	Lint {
+		/// Set mismatching and missing requirements to the member's current version,
+		/// dev-dependencies without a `version` are left as they are
+		#[arg(long)]
+		fix: bool,
+	},
+	/// Rewrite all requirements on workspace members in the configured `requirement-style`
+	///
+	/// Requirements are set to the member's current version.
+	NormalizeReqs {
+		/// Use this style instead of the configured one
+		#[arg(long, value_enum)]
+		style: Option<RequirementStyle>,
+	},
+}
+
+impl VersionCommand {
+	fn pkg_opts_mut(&mut self) -> Option<&mut PackageSelectOptions> {
+		Some(match self {
+			VersionCommand::Release { pkg_opts, .. } |
+			VersionCommand::BumpBreaking { pkg_opts, .. } |
+			VersionCommand::BumpToDev { pkg_opts, .. } |
+			VersionCommand::BumpPre { pkg_opts, .. } |
+			VersionCommand::Promote { pkg_opts, .. } |
+			VersionCommand::BumpPatch { pkg_opts, .. } |
+			VersionCommand::BumpMinor { pkg_opts, .. } |
+			VersionCommand::BumpMajor { pkg_opts, .. } |
+			VersionCommand::Set { pkg_opts, .. } |
+			VersionCommand::SetPre { pkg_opts, .. } |
+			VersionCommand::SetBuild { pkg_opts, .. } |
+			VersionCommand::Nightly { pkg_opts, .. } => pkg_opts,
+			VersionCommand::Lint { .. } | VersionCommand::NormalizeReqs { .. } => return None,
+		})
+	}
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum ConfigCommand {
+	/// Print the effective configuration
+	///
+	/// Shows the `[workspace.metadata.dragons]` defaults with the `[package.metadata.dragons]`
+	/// overrides of the given package applied.
+	Show {
+		/// The package to show the configuration for
+		#[arg(short, long)]
+		package: Option<String>,
+	},
 }
 
 #[derive(clap::Subcommand, Debug)]
@@ -212,27 +436,84 @@ pub enum Command {
 	/// Set a field in all manifests
 	///
 	/// Go through all matching crates and set the field name to value.
-	/// Add the field if it doesn't exists yet.
+	/// Add the field and the tables leading to it if they don't exist yet.
 	Set {
 		#[command(flatten)]
 		pkg_opts: PackageSelectOptions,
 		/// The root key table to look the key up in
-		#[arg(short, long, default_value = "package")]
-		root_key: String,
-		/// Name of the field
+		///
+		/// Defaults to `package` for plain names, dotted names start at the root of the manifest.
+		#[arg(short, long)]
+		root_key: Option<String>,
+		/// Name of the field, may be a dotted path like `package.metadata.docs.rs.all-features`
 		name: String,
 		/// Value to set it, too
+		///
+		/// Parsed as a TOML value, e.g. `true`, `42`, `["a", "b"]` or `{ workspace = true }`,
+		/// anything else is taken as a string. Fields holding a string keep being strings.
+		///
+		/// The placeholders `{name}`, `{version}`, `{path}` (relative to the workspace root),
+		/// `{dir}` and `{description}` are filled in for each package, `{{` and `}}` are literal
+		/// braces.
 		value: String,
+		/// Add the value, or the values of an array, to the array instead of replacing it
+		#[arg(long)]
+		append: bool,
+	},
+	/// Remove a field from all manifests
+	Unset {
+		#[command(flatten)]
+		pkg_opts: PackageSelectOptions,
+		/// The root key table to look the key up in
+		///
+		/// Defaults to `package` for plain names, dotted names start at the root of the manifest.
+		#[arg(short, long)]
+		root_key: Option<String>,
+		/// Name of the field, may be a dotted path like `package.metadata.docs.rs`
+		name: String,
+	},
+	/// Move package fields shared by the members into `[workspace.package]`
+	///
+	/// Fields having the same value in all selected members, or with `--majority` in more than
+	/// half of those setting it, are set in `[workspace.package]` and inherited by the members
+	/// with `field.workspace = true`. Members with a different value keep it and are reported.
+	Inherit {
+		#[command(flatten)]
+		pkg_opts: PackageSelectOptions,
+		/// The fields to inherit
+		#[arg(
+			long = "field",
+			default_values = ["authors", "edition", "homepage", "license", "repository", "rust-version"]
+		)]
+		fields: Vec<String>,
+		/// Inherit the value most members share, if more than half of them do
+		#[arg(long)]
+		majority: bool,
+		/// Replace the inheritance by the values of `[workspace.package]` instead
+		#[arg(long, conflicts_with = "majority")]
+		reverse: bool,
 	},
 	/// Rename a package
 	///
 	/// Update the internally used references to the package by adding an `package = ` entry
-	/// to the dependencies.
+	/// to the dependencies. With `--full`, the dependents use the new name instead, including
+	/// in their features and Rust sources.
 	Rename {
 		/// Name of the field
 		old_name: String,
 		/// Value to set it, too
 		new_name: String,
+		/// Rename the dependency keys, `[features]`, `[workspace.dependencies]` and the crate
+		/// paths in the sources of the dependents instead of aliasing the package
+		#[arg(long)]
+		full: bool,
+		/// Rename the directory of the package as well, updating the workspace `members`
+		#[arg(long, requires = "full")]
+		move_dir: bool,
+		/// Create a crate under the old name re-exporting the renamed one, for downstream users
+		/// of the old name, released alongside it
+		#[arg(long, requires = "full")]
+		keep_facade: bool,
 	},
 	/// Messing with versioning
 	///
@@ -283,6 +564,9 @@ pub enum Command {
 		/// By default we disable dev-dependencies before the run.
 		#[arg(long = "include-dev-deps")]
 		include_dev: bool,
+		/// Disable dev-dependencies, even if `include-dev-deps` is configured
+		#[arg(long = "no-include-dev-deps", conflicts_with = "include_dev")]
+		no_include_dev: bool,
 		#[command(flatten)]
 		pkg_opts: PackageSelectOptions,
 		/// Consider no package matching the criteria an error
@@ -304,6 +588,9 @@ pub enum Command {
 		/// By default we disable dev-dependencies before the run.
 		#[arg(long = "include-dev-deps")]
 		include_dev: bool,
+		/// Disable dev-dependencies, even if `include-dev-deps` is configured
+		#[arg(long = "no-include-dev-deps", conflicts_with = "include_dev")]
+		no_include_dev: bool,
 		#[command(flatten)]
 		pkg_opts: PackageSelectOptions,
 		/// Actually build the package
@@ -338,9 +625,10 @@ pub enum Command {
 		/// Generate readme file for package.
 		///
 		/// Depending on the chosen option, this will generate a Readme
-		/// file from the crate's doc comments (using cargo-readme).
+		/// file from the crate's doc comments (using cargo-readme). Falls back to the
+		/// `readme-mode` configured for the package or the workspace.
 		#[arg(long)]
-		readme_mode: GenerateReadmeMode,
+		readme_mode: Option<GenerateReadmeMode>,
 		/// Consider no package matching the criteria an error
 		#[arg(long)]
 		empty_package_is_failure: bool,
@@ -354,6 +642,9 @@ pub enum Command {
 		/// By default we disable dev-dependencies before the run.
 		#[arg(long = "include-dev-deps")]
 		include_dev: bool,
+		/// Disable dev-dependencies, even if `include-dev-deps` is configured
+		#[arg(long = "no-include-dev-deps", conflicts_with = "include_dev")]
+		no_include_dev: bool,
 		#[command(flatten)]
 		pkg_opts: PackageSelectOptions,
 		/// Actually build the package in check
@@ -364,9 +655,6 @@ pub enum Command {
 		build: bool,
 		/// dry run
 		#[arg(long)]
-		dry_run: bool,
-		/// dry run
-		#[arg(long)]
 		no_check: bool,
 		/// Ensure we have the owner set as well
 		#[arg(long = "owner")]
@@ -392,12 +680,96 @@ pub enum Command {
 		#[arg(long = "dot-graph")]
 		dot_graph: Option<PathBuf>,
 	},
+	/// Report the local and published versions of the packages
+	///
+	/// Lists the latest published release and pre-release of each selected crate, whether the
+	/// local version is published already or lower than a published one and which dependents need
+	/// updates once it's released. Pre-releases only count as higher for local pre-releases.
+	/// Published crates are packaged and compared with the `.crate` published under the same
+	/// version, to find the ones that changed without a version bump, crates failing to package
+	/// are reported as errors.
+	Status {
+		#[command(flatten)]
+		pkg_opts: PackageSelectOptions,
+		/// Fail if any package changed without a version bump, is lower than published or can't be
+		/// compared
+		#[arg(long = "check")]
+		check_only: bool,
+		/// Print the report as a table or as JSON
+		#[arg(long, value_enum, default_value = "table")]
+		format: ReportFormat,
+	},
+	/// Run a named profile
+	///
+	/// Profiles bundle a subcommand with its selection options and flags, they are declared in
+	/// the workspace manifest, e.g. `[workspace.metadata.dragons.profiles.nightly]` with
+	/// `args = ["version", "bump-to-dev", "--pre-tag", "nightly"]`.
+	Run {
+		/// Name of the profile to run
+		#[arg(required_unless_present = "list")]
+		profile: Option<String>,
+		/// List and validate all configured profiles
+		#[arg(long)]
+		list: bool,
+		/// Additional arguments, appended to those of the profile
+		#[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+		extra: Vec<String>,
+	},
+	/// Restore the manifests changed by a previous run
+	///
+	/// Every run changing manifests records their original content, and the directories it moved,
+	/// in a journal under `target/dragons/journal`. Without an id the latest run is undone.
+	Undo {
+		/// Id of the run to undo
+		id: Option<String>,
+		/// List the recorded runs, the latest first
+		#[arg(long, conflicts_with = "id")]
+		list: bool,
+		/// Restore even if the manifests changed since
+		#[arg(long)]
+		force: bool,
+	},
+	/// Inspect the dragons configuration of the workspace
+	Config {
+		#[command(subcommand)]
+		cmd: ConfigCommand,
+	},
 	/// Unify all dependencies to those used in the workspace
 	/// and suggest additional ones.
 	UnifyDeps {
 		#[command(flatten)]
 		pkg_opts: PackageSelectOptions,
 	},
+	/// Format the manifests of the members and the workspace root
+	///
+	/// Sections are put in a canonical order, dependencies sorted alphabetically with
+	/// `workspace = true` first and inline tables spaced consistently.
+	Fmt {
+		#[command(flatten)]
+		pkg_opts: PackageSelectOptions,
+		/// Only check whether the manifests are formatted, fails if they aren't
+		#[arg(long = "check")]
+		check_only: bool,
+	},
+	/// Unify the lints of the members into `[workspace.lints]`
+	///
+	/// Members setting their lints the same as the workspace then inherit them with
+	/// `lints.workspace = true`, others keep their own and are reported.
+	UnifyLints {
+		#[command(flatten)]
+		pkg_opts: PackageSelectOptions,
+		/// Which lints of the members make it into `[workspace.lints]`
+		#[arg(long, value_enum, default_value_t)]
+		policy: LintPolicy,
+		/// Lints members may set differently, as `<tool>::<lint>`, in addition to the
+		/// `allowed-lint-overrides` of the workspace configuration
+		#[arg(long = "allow")]
+		allow: Vec<String>,
+		/// Only check that members don't override lints of `[workspace.lints]` not allowed to,
+		/// fails if they do
+		#[arg(long = "check")]
+		check_only: bool,
+	},
 	/// Check whether packages can be build independently
 	///
 	/// Ensure all packages can be build not only as part of the workspace
@@ -430,6 +802,49 @@ pub enum Command {
 	},
 }
 
+impl Command {
+	/// Apply the workspace configuration as defaults to the command line arguments
+	pub(crate) fn apply_config(&mut self, config: &WorkspaceConfig) -> Result<(), anyhow::Error> {
+		let pkg_opts = match self {
+			Command::Completions { .. } |
+			Command::Rename { .. } |
+			Command::Run { .. } |
+			Command::Undo { .. } |
+			Command::Config { .. } => None,
+			Command::Version { cmd } => cmd.pkg_opts_mut(),
+			Command::Set { pkg_opts, .. } |
+			Command::Unset { pkg_opts, .. } |
+			Command::Inherit { pkg_opts, .. } |
+			Command::AddOwner { pkg_opts, .. } |
+			Command::DeDevDeps { pkg_opts } |
+			Command::CleanDeps { pkg_opts, .. } |
+			Command::Status { pkg_opts, .. } |
+			Command::UnifyDeps { pkg_opts } |
+			Command::UnifyLints { pkg_opts, .. } |
+			Command::Fmt { pkg_opts, .. } |
+			Command::IndependenceCheck { pkg_opts, .. } => Some(pkg_opts),
+			#[cfg(feature = "gen-readme")]
+			Command::GenReadme { pkg_opts, .. } => Some(pkg_opts),
+			Command::ToRelease { pkg_opts, include_dev, no_include_dev, .. } |
+			Command::Check { pkg_opts, include_dev, no_include_dev, .. } => {
+				*include_dev |= config.include_dev_deps && !*no_include_dev;
+				Some(pkg_opts)
+			},
+			Command::Unleash { pkg_opts, include_dev, no_include_dev, add_owner, .. } => {
+				*include_dev |= config.include_dev_deps && !*no_include_dev;
+				if add_owner.is_none() {
+					add_owner.clone_from(&config.owner);
+				}
+				Some(pkg_opts)
+			},
+		};
+		if let Some(pkg_opts) = pkg_opts {
+			pkg_opts.apply_config(config)?;
+		}
+		Ok(())
+	}
+}
+
 #[derive(Debug, clap::Parser)]
 #[command(version, about = "Release the crates of this massiv monorepo")]
 pub struct Args {
@@ -451,6 +866,14 @@ pub struct Args {
 	#[command(flatten)]
 	pub verbosity: clap_verbosity_flag::Verbosity<clap_verbosity_flag::InfoLevel>,
 
+	/// Print the changes to manifests as unified diffs instead of writing them
+	///
+	/// `unleash` doesn't publish on dry runs. The dev-dependencies of the packages to release
+	/// are still deactivated by `check`, `to-release` and `unleash`, as the release order depends
+	/// on it.
+	#[arg(long, global = true)]
+	pub dry_run: bool,
+
 	#[command(subcommand)]
 	pub cmd: Command,
 }
@@ -463,6 +886,32 @@ fn verify_readme_feature() -> anyhow::Result<()> {
 	}
 }
 
+/// Parse the arguments of a named profile, with `extra` arguments appended
+///
+/// The profile arguments are parsed by the same [`Args`] as the command line, a profile can't
+/// run another profile nor point to another workspace.
+fn profile_args(
+	name: &str,
+	profile: &ProfileConfig,
+	extra: &[String],
+) -> Result<Args, anyhow::Error> {
+	let argv = std::iter::once("cargo-dragons")
+		.chain(profile.args.iter().map(String::as_str))
+		.chain(extra.iter().map(String::as_str));
+	let args = Args::try_parse_from(argv)
+		.map_err(|e| anyhow::anyhow!("Invalid profile `{}`: {}", name, e.render()))?;
+	if let Command::Run { .. } = args.cmd {
+		anyhow::bail!("Invalid profile `{}`: profiles can't run other profiles", name);
+	}
+	if args.manifest_path != Path::new(".") {
+		anyhow::bail!(
+			"Invalid profile `{}`: profiles run in the workspace defining them, drop `--manifest-path`",
+			name
+		);
+	}
+	Ok(args)
+}
+
 //TODO: Refactor this implementation to be a bit more readable.
 pub fn run(args: Args) -> Result<(), anyhow::Error> {
 	pretty_env_logger::init();
@@ -475,15 +924,6 @@ pub fn run(args: Args) -> Result<(), anyhow::Error> {
 	gctx.values()?;
 	gctx.load_credentials()?;
 
-	let get_token = |t| -> Result<Option<Secret<String>>, anyhow::Error> {
-		Ok(match t {
-			None => gctx
-				.get_string("registry.token")?
-				.map(|token_json_val| Secret::from(token_json_val.val)),
-			_ => t,
-		})
-	};
-
 	gctx.shell()
 		.set_verbosity(match args.verbosity.log_level().unwrap_or(log::Level::Error) {
 			log::Level::Trace | log::Level::Debug => Verbosity::Verbose,
@@ -500,7 +940,30 @@ pub fn run(args: Args) -> Result<(), anyhow::Error> {
 		fs::canonicalize(path)?
 	};
 
-	let mut ws = Workspace::new(&root_manifest, &gctx).context("Reading workspace failed")?;
+	execute(&gctx, &root_manifest, args.cmd, args.dry_run)
+}
+
+/// Run the given command against the workspace at `root_manifest`
+fn execute(
+	gctx: &GlobalContext,
+	root_manifest: &Path,
+	mut cmd: Command,
+	dry_run: bool,
+) -> Result<(), anyhow::Error> {
+	let get_token = |t| -> Result<Option<Secret<String>>, anyhow::Error> {
+		Ok(match t {
+			None => gctx
+				.get_string("registry.token")?
+				.map(|token_json_val| Secret::from(token_json_val.val)),
+			_ => t,
+		})
+	};
+
+	let mut ws = Workspace::new(root_manifest, gctx).context("Reading workspace failed")?;
+
+	let ws_config = config::workspace_config(&ws)?;
+	cmd.apply_config(&ws_config)?;
+	let editor = ManifestEditor::new(&ws, dry_run)?;
 
 	let maybe_patch =
 		|ws, shouldnt_patch, predicate: &dyn Fn(&Package) -> bool| -> anyhow::Result<Workspace> {
@@ -511,16 +974,19 @@ pub fn run(args: Args) -> Result<(), anyhow::Error> {
 			gctx.shell().status("Preparing", "Disabling Dev Dependencies")?;
 
 			commands::deactivate_dev_dependencies(
+				&editor,
 				ws.members()
 					.filter(|p| predicate(p) && gctx.shell().status("Patching", p.name()).is_ok()),
 			)?;
+			// the release order depends on it, so this is written even on dry runs
+			editor.apply()?;
 			// assure to re-read the workspace, otherwise `fn to_release` will still find cycles
 			// (rightfully so!)
-			Workspace::new(&root_manifest, &gctx).context("Reading workspace failed")
+			Workspace::new(root_manifest, gctx).context("Reading workspace failed")
 		};
 
 	//TODO: Seperate matching from Command implementations to make this a more readable codebase
-	match args.cmd {
+	let result = match cmd {
 		Command::Completions { shell } => {
 			let sink = &mut std::io::stdout();
 			let mut app = <Args as clap::CommandFactory>::command();
@@ -529,64 +995,153 @@ pub fn run(args: Args) -> Result<(), anyhow::Error> {
 			Ok(())
 		},
 		Command::CleanDeps { pkg_opts, check_only } => {
-			let predicate = make_pkg_predicate(&gctx, &ws, pkg_opts)?;
-			commands::clean_up_unused_dependencies(&gctx, &ws, predicate, check_only)
+			let predicate = make_pkg_predicate(gctx, &ws, pkg_opts)?;
+			commands::clean_up_unused_dependencies(gctx, &ws, &editor, predicate, check_only)
 		},
 		Command::AddOwner { owner, token, pkg_opts } => {
 			let token = get_token(token.map(Secret::from))?;
-			let predicate = make_pkg_predicate(&gctx, &ws, pkg_opts)?;
+			let predicate = make_pkg_predicate(gctx, &ws, pkg_opts)?;
 
 			for pkg in ws.members().filter(|p| predicate(p)) {
-				commands::add_owner(&gctx, pkg, owner.clone(), token.clone())?;
+				commands::add_owner(gctx, pkg, owner.clone(), token.clone())?;
 			}
 			Ok(())
 		},
-		Command::Set { root_key, name, value, pkg_opts } => {
-			if name == "name" {
+		Command::Set { root_key, name, value, append, pkg_opts } => {
+			let path = commands::field_path(root_key.as_deref(), &name)?;
+			if path == ["package", "name"] {
 				anyhow::bail!("To change the name please use the rename command!");
 			}
-			let predicate = make_pkg_predicate(&gctx, &ws, pkg_opts)?;
-			let type_value =
-				if let Ok(v) = bool::from_str(&value).map_err(|_| i64::from_str(&value)) {
-					Value::from(v)
-				} else {
-					Value::from(value)
-				};
+			let predicate = make_pkg_predicate(gctx, &ws, pkg_opts)?;
 
 			commands::set_field(
+				&editor,
+				ws.root(),
 				ws.members().filter(|p| {
 					predicate(p) && gctx.shell().status("Setting on", p.name()).is_ok()
 				}),
-				root_key,
-				name,
-				type_value,
+				&path,
+				&value,
+				append,
 			)
 		},
+		Command::Unset { root_key, name, pkg_opts } => {
+			let path = commands::field_path(root_key.as_deref(), &name)?;
+			if path == ["package", "name"] {
+				anyhow::bail!("Packages can't be without a name!");
+			}
+			let predicate = make_pkg_predicate(gctx, &ws, pkg_opts)?;
+
+			commands::unset_field(
+				&editor,
+				ws.members().filter(|p| {
+					predicate(p) && gctx.shell().status("Unsetting on", p.name()).is_ok()
+				}),
+				&path,
+			)
+		},
+		Command::Inherit { pkg_opts, fields, majority, reverse } => {
+			let predicate = make_pkg_predicate(gctx, &ws, pkg_opts)?;
+			if reverse {
+				commands::disinherit(gctx, &ws, &editor, predicate, &fields)
+			} else {
+				commands::inherit(gctx, &ws, &editor, predicate, &fields, majority)
+			}
+		},
+		Command::Status { pkg_opts, check_only, format } => {
+			let registry_cache = pkg_opts.registry_cache.clone();
+			let predicate = make_pkg_predicate(gctx, &ws, pkg_opts)?;
+			commands::status(gctx, &ws, predicate, &registry_cache, format, check_only)
+		},
+		Command::Run { profile, list, extra } => {
+			if list {
+				for (name, profile) in ws_config.profiles.iter() {
+					let state = match profile_args(name, profile, &[]) {
+						Ok(_) => profile.args.join(" "),
+						Err(e) => format!("{:#}", e),
+					};
+					match &profile.description {
+						Some(description) => println!("{name}: {description}\n    {state}"),
+						None => println!("{name}:\n    {state}"),
+					}
+				}
+				return Ok(());
+			}
+			let Some(name) = profile else {
+				anyhow::bail!("Please name the profile to run or use `--list` to show them all")
+			};
+			let profile = ws_config.profiles.get(&name).ok_or_else(|| {
+				anyhow::anyhow!("No profile `{}` in `[workspace.metadata.dragons.profiles]`", name)
+			})?;
+			let args = profile_args(&name, profile, &extra)?;
+			gctx.shell().status(
+				"Running",
+				format!(
+					"{}: {}",
+					name,
+					Vec::from_iter(profile.args.iter().chain(&extra).map(String::as_str)).join(" ")
+				),
+			)?;
+			execute(gctx, root_manifest, args.cmd, dry_run || args.dry_run)
+		},
+		Command::Undo { id, list, force } => commands::undo(gctx, &ws, &editor, list, id, force),
+		Command::Config { cmd: ConfigCommand::Show { package } } => {
+			let pkg_config = match package {
+				Some(name) => {
+					let pkg = members_deep(gctx, &ws)
+						.into_iter()
+						.find(|p| p.name().as_str() == name)
+						.ok_or_else(|| anyhow::anyhow!("No package named {} found", name))?;
+					Some(config::package_config(&pkg)?)
+				},
+				None => None,
+			};
+			print!("{}", toml::to_string_pretty(&ws_config.effective(pkg_config.as_ref()))?);
+			Ok(())
+		},
 		Command::UnifyDeps { pkg_opts } => {
-			let predicate = make_pkg_predicate(&gctx, &ws, pkg_opts)?;
-			commands::unify_dependencies(&gctx, &mut ws, predicate)?;
+			let predicate = make_pkg_predicate(gctx, &ws, pkg_opts)?;
+			commands::unify_dependencies(gctx, &mut ws, &editor, predicate)?;
 			Ok(())
 		},
-		Command::Rename { old_name, new_name } => {
+		Command::Fmt { pkg_opts, check_only } => {
+			let predicate = make_pkg_predicate(gctx, &ws, pkg_opts)?;
+			commands::format_manifests(gctx, &ws, &editor, predicate, check_only)
+		},
+		Command::UnifyLints { pkg_opts, policy, mut allow, check_only } => {
+			allow.extend(ws_config.allowed_lint_overrides.iter().cloned());
+			let predicate = make_pkg_predicate(gctx, &ws, pkg_opts)?;
+			commands::unify_lints(gctx, &ws, &editor, predicate, policy, &allow, check_only)
+		},
+		Command::Rename { old_name, new_name, full: true, move_dir, keep_facade } =>
+			commands::rename_fully(gctx, &ws, &editor, &old_name, &new_name, move_dir, keep_facade),
+		Command::Rename { old_name, new_name, .. } => {
 			let predicate = |p: &Package| p.name().to_string().trim() == old_name;
 			let renamer = |_p: &Package| Some(new_name.clone());
 
-			commands::rename(&gctx, &ws, predicate, renamer)
+			commands::rename(gctx, &ws, &editor, predicate, renamer)
 		},
 		Command::Version { cmd } => {
-			commands::adjust_version(&gctx, &ws, cmd)?;
+			commands::adjust_version(gctx, &ws, &editor, cmd)?;
 			Ok(())
 		},
 		Command::DeDevDeps { pkg_opts } => {
-			let predicate = make_pkg_predicate(&gctx, &ws, pkg_opts)?;
-			let _ = maybe_patch(ws, false, &predicate)?;
-			Ok(())
+			let predicate = make_pkg_predicate(gctx, &ws, pkg_opts)?;
+			commands::deactivate_dev_dependencies(
+				&editor,
+				ws.members()
+					.filter(|p| predicate(p) && gctx.shell().status("Patching", p.name()).is_ok()),
+			)
 		},
-		Command::ToRelease { include_dev, pkg_opts, empty_package_is_failure, dot_graph } => {
-			let predicate = make_pkg_predicate(&gctx, &ws, pkg_opts)?;
+		Command::ToRelease {
+			include_dev, pkg_opts, empty_package_is_failure, dot_graph, ..
+		} => {
+			let selector = make_pkg_selector(gctx, &ws, pkg_opts)?;
+			let predicate = |p: &Package| selector.is_selected(p);
 			let ws = maybe_patch(ws, include_dev, &predicate)?;
 
-			let packages = commands::packages_to_release(&gctx, &ws, predicate, dot_graph)?;
+			let packages = commands::packages_to_release(gctx, &ws, predicate, dot_graph)?;
+			selector.explain(gctx, &ws, Some(&packages))?;
 			handle_empty_package_is_failures(&packages, empty_package_is_failure)?;
 
 			println!(
@@ -604,32 +1159,38 @@ pub fn run(args: Args) -> Result<(), anyhow::Error> {
 			check_readme,
 			empty_package_is_failure,
 			dot_graph,
+			..
 		} => {
 			if check_readme {
 				verify_readme_feature()?;
 			}
+			commands::check_version_groups(gctx, &ws)?;
+			commands::lint_versions(gctx, &ws, &editor, false)?;
 
-			let predicate = make_pkg_predicate(&gctx, &ws, pkg_opts)?;
+			let selector = make_pkg_selector(gctx, &ws, pkg_opts)?;
+			let predicate = |p: &Package| selector.is_selected(p);
 			let ws = maybe_patch(ws, include_dev, &predicate)?;
 
-			let packages = commands::packages_to_release(&gctx, &ws, predicate, dot_graph)?;
+			let packages = commands::packages_to_release(gctx, &ws, predicate, dot_graph)?;
+			selector.explain(gctx, &ws, Some(&packages))?;
 			handle_empty_package_is_failures(&packages, empty_package_is_failure)?;
 
-			commands::check_packages(&gctx, &packages, &ws, build, check_readme)
+			commands::check_packages(gctx, &packages, &ws, build, check_readme)
 		},
 		#[cfg(feature = "gen-readme")]
 		Command::GenReadme { pkg_opts, readme_mode, empty_package_is_failure } => {
-			let predicate = make_pkg_predicate(&gctx, &ws, pkg_opts)?;
+			let selector = make_pkg_selector(gctx, &ws, pkg_opts)?;
+			let predicate = |p: &Package| selector.is_selected(p);
 			let ws = maybe_patch(ws, false, &predicate)?;
 
-			let packages = commands::packages_to_release(&ws, predicate, None)?;
+			let packages = commands::packages_to_release(gctx, &ws, predicate, None)?;
+			selector.explain(gctx, &ws, Some(&packages))?;
 			handle_empty_package_is_failures(&packages, empty_package_is_failure)?;
 
-			commands::gen_all_readme(&gctx, packages, &ws, readme_mode)
+			commands::gen_all_readme(gctx, packages, &ws, &editor, readme_mode, &ws_config)
 		},
 
 		Command::Unleash {
-			dry_run,
 			no_check,
 			token,
 			include_dev,
@@ -639,11 +1200,14 @@ pub fn run(args: Args) -> Result<(), anyhow::Error> {
 			check_readme,
 			empty_package_is_failure,
 			dot_graph,
+			..
 		} => {
-			let predicate = make_pkg_predicate(&gctx, &ws, pkg_opts)?;
+			let selector = make_pkg_selector(gctx, &ws, pkg_opts)?;
+			let predicate = |p: &Package| selector.is_selected(p);
 			let ws = maybe_patch(ws, include_dev, &predicate)?;
 
-			let packages = commands::packages_to_release(&gctx, &ws, predicate, dot_graph)?;
+			let packages = commands::packages_to_release(gctx, &ws, predicate, dot_graph)?;
+			selector.explain(gctx, &ws, Some(&packages))?;
 			handle_empty_package_is_failures(&packages, empty_package_is_failure)?;
 
 			if !no_check {
@@ -651,7 +1215,7 @@ pub fn run(args: Args) -> Result<(), anyhow::Error> {
 					verify_readme_feature()?;
 				}
 
-				commands::check_packages(&gctx, &packages, &ws, build, check_readme)?;
+				commands::check_packages(gctx, &packages, &ws, build, check_readme)?;
 			}
 
 			gctx.shell().status(
@@ -661,16 +1225,16 @@ pub fn run(args: Args) -> Result<(), anyhow::Error> {
 			)?;
 
 			let token = get_token(token.map(Secret::from))?;
-			commands::release(&gctx, packages, ws, dry_run, token, add_owner)
+			commands::release(gctx, packages, ws, dry_run, token, add_owner)
 		},
 		Command::IndependenceCheck { mode: modes, context, pkg_opts, failfast } => {
-			let predicate = make_pkg_predicate(&gctx, &ws, pkg_opts)?;
+			let predicate = make_pkg_predicate(gctx, &ws, pkg_opts)?;
 
 			let packages = Vec::<Package>::from_iter(
-				members_deep(&gctx, &ws).iter().filter(|p| predicate(p)).cloned(),
+				members_deep(gctx, &ws).iter().filter(|p| predicate(p)).cloned(),
 			);
 			let opts = cargo::ops::PackageOpts {
-				gctx: &gctx,
+				gctx,
 				verify: false,
 				check_metadata: false,
 				list: false,
@@ -686,7 +1250,10 @@ pub fn run(args: Args) -> Result<(), anyhow::Error> {
 				keep_going: !failfast,
 			};
 
-			commands::independence_check(&gctx, packages, &opts, ws, modes, context)
+			commands::independence_check(gctx, packages, &opts, ws, modes, context)
 		},
-	}
+	};
+	// all manifests are written at once, only if the command succeeded
+	result?;
+	editor.commit()
 }
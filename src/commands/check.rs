@@ -103,6 +103,7 @@ pub(crate) fn run_check_inplace<'a>(
 	Ok(workspace)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn run_check_ephemeral<'a>(
 	gctx: &'a GlobalContext,
 	ws: &Workspace<'a>,
@@ -22,11 +22,11 @@ pub enum IndependenceCtx {
 	Ephemeral,
 }
 
-impl ToString for IndependenceCtx {
-	fn to_string(&self) -> String {
+impl std::fmt::Display for IndependenceCtx {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		match self {
-			Self::InPlace => String::from("Inplace"),
-			Self::Ephemeral => String::from("Ephemeral"),
+			Self::InPlace => write!(f, "Inplace"),
+			Self::Ephemeral => write!(f, "Ephemeral"),
 		}
 	}
 }
@@ -93,7 +93,7 @@ pub fn independence_check(
 		"Processing",
 		format!(
 			"Running independence check using {} context for {} packages",
-			context.to_string(),
+			context,
 			packages.len()
 		),
 		&style_from_color(AnsiColor::Magenta),
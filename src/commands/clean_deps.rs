@@ -1,4 +1,7 @@
-use crate::util::{edit_each, edit_each_dep, members_deep, DependencyAction};
+use crate::{
+	editor::ManifestEditor,
+	util::{edit_each, edit_each_dep, members_deep, DependencyAction},
+};
 use cargo::{
 	core::{package::Package, Workspace},
 	GlobalContext,
@@ -8,6 +11,7 @@ use std::process::Command;
 pub fn clean_up_unused_dependencies<P>(
 	gctx: &GlobalContext,
 	ws: &Workspace<'_>,
+	editor: &ManifestEditor,
 	predicate: P,
 	check_only: bool,
 ) -> Result<(), anyhow::Error>
@@ -15,35 +19,36 @@ where
 	P: Fn(&Package) -> bool,
 {
 	// inspired by https://gist.github.com/sinkuu/8083240257c485c9f928744b41bbac98
-	let total = edit_each(members_deep(gctx, ws).iter().filter(|p| predicate(p)), |p, doc| {
-		gctx.shell().status("Checking", p.name())?;
-		let source_path = p.root();
-		let root = doc.as_table_mut();
-		Ok(edit_each_dep(root, |p_name, alias, _table, _| {
-			let name = alias.unwrap_or(p_name);
-			let found = Command::new("rg")
-				.args(["--type", "rust"])
-				.arg("-qw")
-				.arg(name.replace('-', "_"))
-				.arg(source_path)
-				.status()
-				.unwrap()
-				.success();
+	let total =
+		edit_each(editor, members_deep(gctx, ws).iter().filter(|p| predicate(p)), |p, doc| {
+			gctx.shell().status("Checking", p.name())?;
+			let source_path = p.root();
+			let root = doc.as_table_mut();
+			Ok(edit_each_dep(root, |p_name, alias, _table, _| {
+				let name = alias.unwrap_or(p_name);
+				let found = Command::new("rg")
+					.args(["--type", "rust"])
+					.arg("-qw")
+					.arg(name.replace('-', "_"))
+					.arg(source_path)
+					.status()
+					.unwrap()
+					.success();
 
-			if !found {
-				if check_only {
-					gctx.shell().status("Not needed", name).expect("Writing to Shell works");
-					DependencyAction::Untouched
+				if !found {
+					if check_only {
+						gctx.shell().status("Not needed", name).expect("Writing to Shell works");
+						DependencyAction::Untouched
+					} else {
+						gctx.shell().status("Removed", name).expect("Writing to Shell works");
+						DependencyAction::Remove
+					}
 				} else {
-					gctx.shell().status("Removed", name).expect("Writing to Shell works");
-					DependencyAction::Remove
+					DependencyAction::Untouched
 				}
-			} else {
-				DependencyAction::Untouched
-			}
-		}))
-	})
-	.map(|v| v.iter().sum::<u32>());
+			}))
+		})
+		.map(|v| v.iter().sum::<u32>());
 
 	match total? {
 		t if t > 0 && check_only => {
@@ -1,11 +1,26 @@
-use crate::util::edit_each;
+use crate::{editor::ManifestEditor, util::edit_each};
 use cargo::core::package::Package;
+use toml_edit::Item;
 
-/// Deactivate the Dev Dependencies Section of the given toml
-pub fn deactivate_dev_dependencies<'a, I>(iter: I) -> Result<(), anyhow::Error>
+/// Deactivate the Dev Dependencies Sections of the given toml, including the target specific ones
+pub fn deactivate_dev_dependencies<'a, I>(
+	editor: &ManifestEditor,
+	iter: I,
+) -> Result<(), anyhow::Error>
 where
 	I: Iterator<Item = &'a Package>,
 {
-	edit_each(iter, |_, doc| Ok(doc.as_table_mut().remove("dev-dependencies")))?;
+	edit_each(editor, iter, |_, doc| {
+		let root = doc.as_table_mut();
+		root.remove("dev-dependencies");
+		if let Some(targets) = root.get_mut("target").and_then(Item::as_table_mut) {
+			for (_, target) in targets.iter_mut() {
+				if let Some(target) = target.as_table_mut() {
+					target.remove("dev-dependencies");
+				}
+			}
+		}
+		Ok(())
+	})?;
 	Ok(())
 }
@@ -0,0 +1,215 @@
+use crate::{
+	editor::ManifestEditor,
+	util::{dependency_tables, edit_each, edit_root, workspace_first},
+};
+use cargo::{
+	core::{package::Package, Workspace},
+	GlobalContext,
+};
+use std::collections::BTreeMap;
+use toml_edit::{DocumentMut, Item, Table, Value};
+
+/// The order of the top level sections, others follow in their current order
+const SECTIONS: &[&str] = &[
+	"package",
+	"lib",
+	"bin",
+	"example",
+	"test",
+	"bench",
+	"badges",
+	"features",
+	"dependencies",
+	"dev-dependencies",
+	"build-dependencies",
+	"target",
+	"lints",
+	"workspace",
+	"patch",
+	"replace",
+	"profile",
+];
+
+fn rank(section: &str) -> usize {
+	let section = section.replace('_', "-");
+	SECTIONS.iter().position(|s| *s == section).unwrap_or(SECTIONS.len())
+}
+
+/// Call `f` on every table below `item`, including those of arrays of tables
+fn visit_tables(item: &mut Item, f: &mut dyn FnMut(&mut Table)) {
+	let mut visit = |table: &mut Table| {
+		f(table);
+		for (_, child) in table.iter_mut() {
+			visit_tables(child, f);
+		}
+	};
+	match item {
+		Item::Table(table) => visit(table),
+		Item::ArrayOfTables(tables) => tables.iter_mut().for_each(visit),
+		_ => {},
+	}
+}
+
+/// Reorder the tables of the manifest by [`SECTIONS`], keeping the order within each section
+fn order_sections(doc: &mut DocumentMut) {
+	let mut next = 1;
+	for (_, item) in doc.as_table_mut().iter_mut() {
+		visit_tables(item, &mut |table| {
+			next = next.max(table.position().unwrap_or_default() + 1);
+		});
+	}
+
+	// (rank of the section, current position) of every table
+	let mut tables = Vec::new();
+	for (key, item) in doc.as_table_mut().iter_mut() {
+		let rank = rank(key.get());
+		visit_tables(item, &mut |table| {
+			let position = table.position().unwrap_or_else(|| {
+				next += 1;
+				next
+			});
+			table.set_position(position);
+			tables.push((rank, position));
+		});
+	}
+	tables.sort();
+	let positions = BTreeMap::from_iter(
+		tables.into_iter().enumerate().map(|(new, (_, current))| (current, new + 1)),
+	);
+
+	for (_, item) in doc.as_table_mut().iter_mut() {
+		visit_tables(item, &mut |table| {
+			let position = table.position().expect("All tables got a position above. qed");
+			table.set_position(positions[&position]);
+		});
+	}
+}
+
+/// Sort the dependencies alphabetically, with `workspace = true` first in each entry and
+/// inline tables spaced like `{ workspace = true, features = ["std"] }`
+fn format_dependencies(deps: &mut Table) {
+	deps.sort_values();
+	for (_, dep) in deps.iter_mut() {
+		match dep {
+			Item::Value(Value::InlineTable(info)) => {
+				workspace_first(info);
+				info.fmt();
+			},
+			Item::Table(info) => workspace_first(info),
+			_ => {},
+		}
+	}
+}
+
+/// Bring the manifest into its canonical form
+pub fn format_manifest(doc: &mut DocumentMut) {
+	for (_, deps) in dependency_tables(doc.as_table_mut()) {
+		format_dependencies(deps);
+	}
+	if let Some(deps) = doc
+		.get_mut("workspace")
+		.and_then(|w| w.get_mut("dependencies"))
+		.and_then(Item::as_table_mut)
+	{
+		format_dependencies(deps);
+	}
+	order_sections(doc);
+}
+
+/// Format the manifests of the selected members and the workspace root. With `check_only`,
+/// fail if any of them isn't formatted instead of changing them.
+pub fn format_manifests<P>(
+	gctx: &GlobalContext,
+	ws: &Workspace<'_>,
+	editor: &ManifestEditor,
+	predicate: P,
+	check_only: bool,
+) -> Result<(), anyhow::Error>
+where
+	P: Fn(&Package) -> bool,
+{
+	if check_only {
+		let mut manifests = Vec::from_iter(
+			ws.members().filter(|p| predicate(p)).map(|p| p.manifest_path().to_path_buf()),
+		);
+		if !manifests.iter().any(|m| m == ws.root_manifest()) {
+			manifests.push(ws.root_manifest().to_path_buf());
+		}
+		let mut unformatted = Vec::new();
+		for manifest in manifests {
+			let content = editor.read(&manifest)?;
+			let mut doc: DocumentMut = content.parse()?;
+			format_manifest(&mut doc);
+			if doc.to_string() != content {
+				unformatted.push(manifest.display().to_string());
+			}
+		}
+		if !unformatted.is_empty() {
+			anyhow::bail!(
+				"Aborting: {} aren't formatted, run `cargo dragons fmt`",
+				unformatted.join(", ")
+			);
+		}
+		return Ok(());
+	}
+
+	edit_each(editor, ws.members().filter(|p| predicate(p)), |p, doc| {
+		let before = doc.to_string();
+		format_manifest(doc);
+		if doc.to_string() != before {
+			gctx.shell().status("Formatted", p.name())?;
+		}
+		Ok(())
+	})?;
+	edit_root(editor, ws, |doc| {
+		format_manifest(doc);
+		Ok(())
+	})?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn canonical_form() {
+		let mut doc: DocumentMut = r#"
+[dependencies]
+serde = {version="1",features = ["derive"]}
+anyhow = "1"
+codec = { features = ["std"], workspace = true }
+
+[features]
+std = []
+
+[package]
+name = "a"
+version = "0.1.0"
+
+[dev-dependencies.tempdir]
+version = "0.3"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+"#
+		.parse()
+		.unwrap();
+		format_manifest(&mut doc);
+		let formatted = doc.to_string();
+
+		let at = |needle: &str| formatted.find(needle).unwrap();
+		assert!(at("[package]") < at("[features]"));
+		assert!(at("[features]") < at("[dependencies]"));
+		assert!(at("[dependencies]") < at("[dev-dependencies.tempdir]"));
+		assert!(at("[dev-dependencies.tempdir]") < at("[target.'cfg(unix)'.dependencies]"));
+		assert!(at("anyhow") < at("codec") && at("codec") < at("serde"));
+		assert!(formatted.contains(r#"serde = { version = "1", features = ["derive"] }"#));
+		assert!(formatted.contains(r#"codec = { workspace = true, features = ["std"] }"#));
+
+		// formatting is stable
+		let mut again: DocumentMut = formatted.parse().unwrap();
+		format_manifest(&mut again);
+		assert_eq!(again.to_string(), formatted);
+	}
+}
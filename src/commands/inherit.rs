@@ -0,0 +1,266 @@
+use crate::{
+	editor::ManifestEditor,
+	util::{edit_each, edit_root, normalized},
+};
+use cargo::{
+	core::{package::Package, Workspace},
+	GlobalContext,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use toml_edit::{DocumentMut, InlineTable, Item, Table, Value};
+
+/// The fields of `[package]` members can inherit from `[workspace.package]`. Paths like `readme`,
+/// `license-file`, `include` and `exclude` are relative to the manifest setting them and can't
+/// be moved between manifests as they are, so they are left out.
+pub const INHERITABLE: &[&str] = &[
+	"authors",
+	"categories",
+	"description",
+	"documentation",
+	"edition",
+	"homepage",
+	"keywords",
+	"license",
+	"publish",
+	"repository",
+	"rust-version",
+	"version",
+];
+
+fn check_fields(fields: &[String]) -> Result<(), anyhow::Error> {
+	for field in fields {
+		if !INHERITABLE.contains(&field.as_str()) {
+			anyhow::bail!("`{}` can't be inherited, only {} can", field, INHERITABLE.join(", "));
+		}
+	}
+	Ok(())
+}
+
+/// Whether the item is `{ workspace = true }` or `field.workspace = true`
+pub fn is_inherited(item: &Item) -> bool {
+	item.get("workspace").and_then(Item::as_bool).unwrap_or_default()
+}
+
+/// `field.workspace = true`
+pub fn inheritance() -> Item {
+	let mut table = InlineTable::new();
+	table.insert("workspace", Value::from(true));
+	table.set_dotted(true);
+	Item::Value(Value::InlineTable(table))
+}
+
+fn workspace_package(ws: &Workspace<'_>, editor: &ManifestEditor) -> Result<Table, anyhow::Error> {
+	let root: DocumentMut = editor.read(ws.root_manifest())?.parse()?;
+	Ok(root
+		.get("workspace")
+		.and_then(|w| w.get("package"))
+		.and_then(Item::as_table)
+		.cloned()
+		.unwrap_or_default())
+}
+
+fn package_table(editor: &ManifestEditor, pkg: &Package) -> Result<Option<Table>, anyhow::Error> {
+	let doc: DocumentMut = editor.read(pkg.manifest_path())?.parse()?;
+	Ok(doc.get("package").and_then(Item::as_table).cloned())
+}
+
+/// Move the `fields` sharing a value in all selected members, or with `majority` in more than
+/// half of those setting it, into `[workspace.package]` and let the members inherit it. Members
+/// with a different value keep it and are reported.
+pub fn inherit<P>(
+	gctx: &GlobalContext,
+	ws: &Workspace<'_>,
+	editor: &ManifestEditor,
+	predicate: P,
+	fields: &[String],
+	majority: bool,
+) -> Result<(), anyhow::Error>
+where
+	P: Fn(&Package) -> bool,
+{
+	check_fields(fields)?;
+	let members = Vec::from_iter(ws.members().filter(|p| predicate(p)));
+	let workspace = workspace_package(ws, editor)?;
+
+	// field -> normalized value -> (value, members setting it)
+	let mut values = BTreeMap::<&str, BTreeMap<String, (Value, BTreeSet<String>)>>::new();
+	for pkg in members.iter() {
+		let Some(package) = package_table(editor, pkg)? else { continue };
+		for field in fields {
+			match package.get(field) {
+				Some(item) if is_inherited(item) => {},
+				Some(Item::Value(value)) => {
+					values
+						.entry(field.as_str())
+						.or_default()
+						.entry(normalized(value))
+						.or_insert_with(|| (value.clone(), BTreeSet::new()))
+						.1
+						.insert(pkg.name().to_string());
+				},
+				_ => {},
+			}
+		}
+	}
+
+	// field -> (value, members to inherit it)
+	let mut plan = BTreeMap::new();
+	for (field, candidates) in values {
+		let total: usize = candidates.values().map(|(_, members)| members.len()).sum();
+		let (key, (value, inheriting)) = candidates
+			.iter()
+			.max_by_key(|(_, (_, members))| members.len())
+			.expect("Only fields set by a member are collected. qed");
+		let shared = if majority { inheriting.len() * 2 > total } else { candidates.len() == 1 };
+		if !shared {
+			gctx.shell().warn(format!(
+				"Not inheriting `{}`, the members set {} different values",
+				field,
+				candidates.len()
+			))?;
+			continue;
+		}
+		if let Some(existing) = workspace.get(field).and_then(Item::as_value) {
+			if &normalized(existing) != key {
+				gctx.shell().warn(format!(
+					"Not inheriting `{}` = {}, `[workspace.package]` sets it to {}",
+					field,
+					key,
+					normalized(existing)
+				))?;
+				continue;
+			}
+		}
+		for (other, (_, members)) in candidates.iter().filter(|(other, _)| *other != key) {
+			for member in members {
+				gctx.shell().warn(format!("{} keeps its own `{}` = {}", member, field, other))?;
+			}
+		}
+		gctx.shell().status(
+			"Inheriting",
+			format!("`{}` = {} in {} packages", field, key, inheriting.len()),
+		)?;
+		plan.insert(field, (value.clone(), inheriting.clone()));
+	}
+
+	if plan.is_empty() {
+		gctx.shell().status("Done", "Nothing to inherit")?;
+		return Ok(());
+	}
+
+	edit_root(editor, ws, |doc| {
+		let workspace = doc
+			.as_table_mut()
+			.entry("workspace")
+			.or_insert_with(|| Item::Table(Table::new()))
+			.as_table_mut()
+			.ok_or_else(|| anyhow::anyhow!("`workspace` isn't a table"))?;
+		let package = workspace
+			.entry("package")
+			.or_insert_with(|| Item::Table(Table::new()))
+			.as_table_mut()
+			.ok_or_else(|| anyhow::anyhow!("`workspace.package` isn't a table"))?;
+		for (field, (value, _)) in plan.iter() {
+			if !package.contains_key(field) {
+				package.insert(field, Item::Value(value.clone().decorated(" ", "")));
+			}
+		}
+		Ok(())
+	})?;
+
+	edit_each(editor, members.iter().copied(), |p, doc| {
+		let Some(package) = doc.get_mut("package").and_then(Item::as_table_mut) else {
+			return Ok(());
+		};
+		for (field, (_, inheriting)) in plan.iter() {
+			if inheriting.contains(p.name().as_str()) {
+				package.insert(field, inheritance());
+			}
+		}
+		Ok(())
+	})?;
+	Ok(())
+}
+
+/// Replace the inheritance of `fields` in the selected members by the values of
+/// `[workspace.package]`, removing those no member inherits anymore.
+pub fn disinherit<P>(
+	gctx: &GlobalContext,
+	ws: &Workspace<'_>,
+	editor: &ManifestEditor,
+	predicate: P,
+	fields: &[String],
+) -> Result<(), anyhow::Error>
+where
+	P: Fn(&Package) -> bool,
+{
+	check_fields(fields)?;
+	let workspace = workspace_package(ws, editor)?;
+
+	edit_each(editor, ws.members().filter(|p| predicate(p)), |p, doc| {
+		let Some(package) = doc.get_mut("package").and_then(Item::as_table_mut) else {
+			return Ok(());
+		};
+		for field in fields {
+			if !package.get(field).map(is_inherited).unwrap_or_default() {
+				continue;
+			}
+			let Some(value) = workspace.get(field).and_then(Item::as_value) else {
+				anyhow::bail!(
+					"{} inherits `{}`, but `[workspace.package]` doesn't set it",
+					p.name(),
+					field
+				)
+			};
+			gctx.shell().status("Disinheriting", format!("`{}` of {}", field, p.name()))?;
+			package.insert(field, Item::Value(value.clone().decorated(" ", "")));
+		}
+		Ok(())
+	})?;
+
+	// drop the values nobody inherits anymore
+	let mut still_inherited = BTreeSet::new();
+	for pkg in ws.members() {
+		let Some(package) = package_table(editor, pkg)? else { continue };
+		still_inherited
+			.extend(fields.iter().filter(|f| package.get(f).map(is_inherited).unwrap_or_default()));
+	}
+	edit_root(editor, ws, |doc| {
+		let Some(workspace) = doc.get_mut("workspace").and_then(Item::as_table_mut) else {
+			return Ok(());
+		};
+		let Some(package) = workspace.get_mut("package").and_then(Item::as_table_mut) else {
+			return Ok(());
+		};
+		for field in fields.iter().filter(|f| !still_inherited.contains(f)) {
+			package.remove(field);
+		}
+		if package.is_empty() {
+			workspace.remove("package");
+		}
+		Ok(())
+	})?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn formatting_is_ignored() {
+		let doc: DocumentMut = r#"
+a = [ "Parity <admin@parity.io>" ]
+b = ["Parity <admin@parity.io>"]
+c = { workspace = true }
+d.workspace = true
+"#
+		.parse()
+		.unwrap();
+		let value = |key| doc.get(key).and_then(Item::as_value).unwrap();
+		assert_eq!(normalized(value("a")), normalized(value("b")));
+		assert!(is_inherited(&doc["c"]));
+		assert!(is_inherited(&doc["d"]));
+		assert!(!is_inherited(&doc["a"]));
+	}
+}
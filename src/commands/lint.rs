@@ -0,0 +1,274 @@
+use crate::{
+	config::{workspace_config, RequirementStyle},
+	editor::ManifestEditor,
+	util::{
+		edit_each_dep, edit_workspace_deps, members_deep, DependencyAction, DependencyEntry,
+		DependencySection,
+	},
+};
+use cargo::{
+	core::{package::Package, Workspace},
+	GlobalContext,
+};
+use semver::{Op, Version, VersionReq};
+use std::{
+	cell::RefCell,
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+	fmt,
+	path::Path,
+};
+use toml_edit::{DocumentMut, Table};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Severity {
+	/// Breaks publishing, fails `version lint` and `check`
+	Error,
+	/// Reported only
+	Warning,
+}
+
+/// A problem found in a manifest
+struct Diagnostic {
+	severity: Severity,
+	/// The package whose manifest has the problem, or the dependency for style mismatches
+	package: String,
+	message: String,
+	/// Whether `--fix` resolved it
+	fixed: bool,
+}
+
+impl fmt::Display for Diagnostic {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}: {}", self.package, self.message)
+	}
+}
+
+/// The operator a requirement is written with, bare requirements count as caret
+fn requirement_style(req: &VersionReq) -> &'static str {
+	match req.comparators.as_slice() {
+		[c] => match c.op {
+			Op::Exact => "exact",
+			Op::Tilde => "tilde",
+			Op::Caret => "caret",
+			Op::Wildcard => "wildcard",
+			_ => "range",
+		},
+		_ => "range",
+	}
+}
+
+/// Lints the dependency entries of a single manifest
+struct Linter<'a> {
+	/// Versions of all members
+	members: &'a HashMap<String, Version>,
+	editor: &'a ManifestEditor,
+	fix: bool,
+	/// The configured `requirement-style` used when fixing, keeps the existing style if unset
+	style: Option<RequirementStyle>,
+	diagnostics: RefCell<Vec<Diagnostic>>,
+	/// Requirement styles used per dependency, and by which packages
+	styles: RefCell<BTreeMap<String, BTreeMap<&'static str, BTreeSet<String>>>>,
+}
+
+impl Linter<'_> {
+	fn report(&self, severity: Severity, package: &str, message: String, fixed: bool) {
+		self.diagnostics.borrow_mut().push(Diagnostic {
+			severity,
+			package: package.to_owned(),
+			message,
+			fixed,
+		});
+	}
+
+	/// Lint the entry for dependency `name` of the manifest of `owner` (with version
+	/// `owner_version`, if it's a package)
+	fn lint_entry(
+		&self,
+		owner: &str,
+		owner_version: Option<&Version>,
+		name: String,
+		mut entry: DependencyEntry<'_>,
+		section: DependencySection,
+	) -> DependencyAction {
+		if entry.contains_key("workspace") {
+			return DependencyAction::Untouched; // inherited, linted in the root manifest
+		}
+
+		let requirement = entry.get_str("version").and_then(|s| VersionReq::parse(s).ok());
+		if let Some(req) = &requirement {
+			self.styles
+				.borrow_mut()
+				.entry(name.clone())
+				.or_default()
+				.entry(requirement_style(req))
+				.or_default()
+				.insert(owner.to_owned());
+		}
+
+		let member_version = match self.members.get(&name) {
+			Some(v) if entry.contains_key("path") => v,
+			_ => return DependencyAction::Untouched, // not internal
+		};
+
+		if !section.is_dev() && !member_version.pre.is_empty() {
+			if let Some(owner_version) = owner_version.filter(|v| v.pre.is_empty()) {
+				self.report(
+					Severity::Warning,
+					owner,
+					format!(
+						"{} requires the pre-release {} {}",
+						owner_version, name, member_version
+					),
+					false,
+				);
+			}
+		}
+
+		if requirement.is_none() && section.is_dev() {
+			// cargo strips those when publishing, fine for dev-dependencies on unpublished members
+			self.report(
+				Severity::Warning,
+				owner,
+				format!(
+					"dev-dependency on {} has no `version`, it is stripped when publishing",
+					name
+				),
+				false,
+			);
+			return DependencyAction::Untouched;
+		}
+
+		let message = match &requirement {
+			Some(req) if req.matches(member_version) => return DependencyAction::Untouched,
+			Some(req) =>
+				format!("requires {} {}, but the member is at {}", name, req, member_version),
+			None => format!("dependency on {} has no `version`, publishing will fail", name),
+		};
+		self.report(Severity::Error, owner, message, self.fix);
+		if !self.fix {
+			return DependencyAction::Untouched;
+		}
+		let style = self
+			.style
+			.or_else(|| entry.get_str("version").map(RequirementStyle::of))
+			.unwrap_or_default();
+		entry.set_requirement(style.requirement(member_version));
+		DependencyAction::Mutated
+	}
+
+	fn lint_manifest<F>(&self, path: &Path, lint: F) -> Result<(), anyhow::Error>
+	where
+		F: FnOnce(&mut Table) -> u32,
+	{
+		let mut doc: DocumentMut = self.editor.read(path)?.parse()?;
+		if lint(doc.as_table_mut()) > 0 {
+			self.editor.write(path, doc.to_string())?;
+		}
+		Ok(())
+	}
+}
+
+/// Check the version requirements on members throughout the workspace.
+///
+/// Reports requirements not matching the member's version, path dependencies without a
+/// `version`, pre-release members required by released ones and dependencies required with
+/// differing styles. Only the first two are errors, unless the dependency without a `version` is
+/// a dev-dependency. With `fix`, the erroneous requirements are set to the member's current
+/// version, in the configured `requirement-style`.
+pub fn lint_versions(
+	gctx: &GlobalContext,
+	ws: &Workspace<'_>,
+	editor: &ManifestEditor,
+	fix: bool,
+) -> Result<(), anyhow::Error> {
+	gctx.shell().status("Linting", "Version requirements")?;
+
+	let mut seen = HashSet::new();
+	let packages = Vec::from_iter(
+		members_deep(gctx, ws)
+			.into_iter()
+			.filter(|p| seen.insert(p.manifest_path().to_path_buf())),
+	);
+	let members = HashMap::<String, Version>::from_iter(
+		packages.iter().map(|p| (p.name().to_string(), p.version().clone())),
+	);
+	let linter = Linter {
+		members: &members,
+		editor,
+		fix,
+		style: workspace_config(ws)?.requirement_style,
+		diagnostics: Default::default(),
+		styles: Default::default(),
+	};
+
+	for pkg in packages.iter() {
+		lint_package(&linter, pkg)?;
+	}
+	linter.lint_manifest(ws.root_manifest(), |root| {
+		edit_workspace_deps(root, |name, _, entry, section| {
+			linter.lint_entry("[workspace.dependencies]", None, name, entry, section)
+		})
+	})?;
+
+	let mut diagnostics = linter.diagnostics.into_inner();
+	for (name, styles) in linter.styles.into_inner() {
+		if styles.len() > 1 {
+			let usages = Vec::from_iter(styles.iter().map(|(style, users)| {
+				format!("{} in {}", style, Vec::from_iter(users.iter().cloned()).join(", "))
+			}));
+			diagnostics.push(Diagnostic {
+				severity: Severity::Warning,
+				package: name,
+				message: format!("required with differing styles: {}", usages.join("; ")),
+				fixed: false,
+			});
+		}
+	}
+
+	let mut errors = 0;
+	for diagnostic in diagnostics.iter() {
+		if diagnostic.fixed {
+			gctx.shell().status("Fixed", diagnostic)?;
+		} else if diagnostic.severity == Severity::Error {
+			errors += 1;
+			gctx.shell().error(diagnostic)?;
+		} else {
+			gctx.shell().warn(diagnostic)?;
+		}
+	}
+	if errors > 0 {
+		anyhow::bail!(
+			"Found {} problems with version requirements, run `version lint --fix` to fix them",
+			errors
+		);
+	}
+	Ok(())
+}
+
+fn lint_package(linter: &Linter<'_>, pkg: &Package) -> Result<(), anyhow::Error> {
+	linter.lint_manifest(pkg.manifest_path(), |root| {
+		edit_each_dep(root, |name, _, entry, section| {
+			// name the section of platform specific entries, the same dependency may be in several
+			let owner = match section.target() {
+				Some(_) => format!("{} {}", pkg.name(), section),
+				None => pkg.name().to_string(),
+			};
+			linter.lint_entry(&owner, Some(pkg.version()), name, entry, section)
+		})
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn bare_requirements_are_caret() {
+		let style = |s: &str| requirement_style(&VersionReq::parse(s).unwrap());
+		assert_eq!(style("1.2"), "caret");
+		assert_eq!(style("^1.2.3"), "caret");
+		assert_eq!(style("=1.2.3"), "exact");
+		assert_eq!(style("~1.2"), "tilde");
+		assert_eq!(style(">=1.2, <2"), "range");
+	}
+}
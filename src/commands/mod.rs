@@ -3,11 +3,17 @@ mod check;
 mod check_independence;
 mod clean_deps;
 mod de_dev_deps;
+mod format;
+mod inherit;
+mod lint;
 mod release;
 mod rename;
 mod set_field;
+mod status;
 mod to_release;
+mod undo;
 mod unifydeps;
+mod unifylints;
 mod version;
 
 pub use add_owner::add_owner;
@@ -15,12 +21,18 @@ pub use check::check_packages;
 pub use check_independence::{independence_check, IndependenceCtx};
 pub use clean_deps::clean_up_unused_dependencies;
 pub use de_dev_deps::deactivate_dev_dependencies;
+pub use format::format_manifests;
+pub use inherit::{disinherit, inherit};
+pub use lint::lint_versions;
 pub use release::release;
-pub use rename::rename;
-pub use set_field::set_field;
+pub use rename::{rename, rename_fully};
+pub use set_field::{field_path, set_field, unset_field};
+pub use status::status;
 pub use to_release::packages_to_release;
+pub use undo::undo;
 pub use unifydeps::unify_dependencies;
-pub use version::adjust_version;
+pub use unifylints::{unify_lints, LintPolicy};
+pub use version::{adjust_version, check_version_groups};
 
 #[cfg(feature = "gen-readme")]
 mod readme;
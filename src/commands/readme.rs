@@ -1,6 +1,14 @@
-use crate::{cli::GenerateReadmeMode, commands};
+use crate::{
+	cli::GenerateReadmeMode,
+	commands,
+	config::{package_config, WorkspaceConfig},
+	editor::ManifestEditor,
+};
 use anyhow::{anyhow, bail, Context, Result};
-use cargo::core::{Manifest, Package, Workspace};
+use cargo::{
+	core::{Manifest, Package, Workspace},
+	GlobalContext,
+};
 use lazy_static::lazy_static;
 use regex::{Captures, Regex};
 use sha1::{Digest as _, Sha1};
@@ -9,7 +17,6 @@ use std::{
 	fs::{self, File},
 	path::{Path, PathBuf},
 };
-use toml_edit::Value;
 
 static DEFAULT_DOC_URI: &str = "https://docs.rs/";
 
@@ -53,7 +60,8 @@ pub fn check_pkg_readme<'a>(
 	let mut pkg_source = find_entrypoint(pkg_path)?;
 	let readme_path = pkg_path.join("README.md");
 
-	gctx.shell()
+	ws.gctx()
+		.shell()
 		.status("Checking", format!("Readme for {}", &pkg_manifest.name()))?;
 
 	let pkg_readme = fs::read_to_string(readme_path.clone());
@@ -77,12 +85,19 @@ pub fn gen_all_readme<'a>(
 	gctx: &GlobalContext,
 	packages: Vec<Package>,
 	ws: &Workspace<'a>,
-	readme_mode: GenerateReadmeMode,
+	editor: &ManifestEditor,
+	readme_mode: Option<GenerateReadmeMode>,
+	ws_config: &WorkspaceConfig,
 ) -> Result<()> {
 	gctx.shell().status("Generating", "Readme files")?;
 	for pkg in packages.into_iter() {
 		let pkg_name = &pkg.name().clone();
-		gen_pkg_readme(gctx, ws, pkg, &readme_mode)
+		let pkg_config = package_config(&pkg)?;
+		let readme_mode =
+			readme_mode.or(ws_config.effective(Some(&pkg_config)).readme_mode).ok_or_else(
+				|| anyhow!("No --readme-mode given nor `readme-mode` configured for {}", pkg_name),
+			)?;
+		gen_pkg_readme(gctx, ws, editor, pkg, &readme_mode)
 			.context(format!("Failure generating Readme for {:}", pkg_name))?
 	}
 
@@ -92,6 +107,7 @@ pub fn gen_all_readme<'a>(
 pub fn gen_pkg_readme<'a>(
 	gctx: &GlobalContext,
 	ws: &Workspace<'a>,
+	editor: &ManifestEditor,
 	pkg: Package,
 	mode: &GenerateReadmeMode,
 ) -> Result<()> {
@@ -111,7 +127,7 @@ pub fn gen_pkg_readme<'a>(
 		(GenerateReadmeMode::IfMissing, Ok(_existing_readme)) => {
 			gctx.shell()
 				.status("Skipping", format!("{}: Readme already exists.", &pkg_name))?;
-			set_readme_field(pkg)?;
+			set_readme_field(editor, ws, pkg)?;
 			Ok(())
 		},
 		(mode, existing_res) => {
@@ -134,7 +150,7 @@ pub fn gen_pkg_readme<'a>(
 			let final_readme =
 				&mut rewrite_doc_links(&pkg_name, &new_readme, doc_uri.map(|x| x.as_str()));
 			let res = fs::write(readme_path, final_readme.as_bytes());
-			set_readme_field(pkg)?;
+			set_readme_field(editor, ws, pkg)?;
 			Ok(res?)
 		},
 	}
@@ -162,12 +178,18 @@ fn generate_readme<'a>(
 	Ok(readme_content)
 }
 
-fn set_readme_field(pkg: Package) -> Result<(), anyhow::Error> {
+fn set_readme_field(
+	editor: &ManifestEditor,
+	ws: &Workspace<'_>,
+	pkg: Package,
+) -> Result<(), anyhow::Error> {
 	commands::set_field(
+		editor,
+		ws.root(),
 		vec![pkg].iter(),
-		"package".to_owned(),
-		"readme".to_owned(),
-		Value::from("README.md"),
+		&["package".to_owned(), "readme".to_owned()],
+		"README.md",
+		false,
 	)
 }
 
@@ -1,4 +1,4 @@
-use crate::commands::add_owner;
+use crate::{commands::add_owner, config::package_config};
 use cargo::{
 	core::{package::Package, resolver::features::CliFeatures, Workspace},
 	ops::{self, publish, PublishOpts},
@@ -6,7 +6,7 @@ use cargo::{
 };
 use cargo_credential::Secret;
 
-use std::{thread, time::Duration};
+use std::{collections::HashSet, thread, time::Duration};
 
 pub fn release(
 	gctx: &GlobalContext,
@@ -57,7 +57,10 @@ pub fn release(
 		let pkg_ws = Workspace::ephemeral(pkg.clone(), gctx, Some(ws.target_dir()), true)?;
 		gctx.shell().status("Publishing", pkg)?;
 		publish(&pkg_ws, &opts)?;
-		if let Some(ref o) = owner {
+		let config = package_config(pkg)?;
+		// the workspace owner may be listed for the package again
+		let mut added = HashSet::new();
+		for o in owner.iter().chain(config.owners.iter()).filter(|o| added.insert(*o)) {
 			add_owner(gctx, pkg, o.clone(), token.clone())?;
 		}
 	}
@@ -1,11 +1,26 @@
-use crate::util::{edit_each, edit_each_dep, members_deep, DependencyAction, DependencyEntry};
+use super::{
+	inherit::{inheritance, is_inherited},
+	set_field::parent_table,
+};
+use crate::{
+	config::workspace_config,
+	editor::ManifestEditor,
+	util::{
+		dependency_tables, edit_each, edit_each_dep, edit_root, members_deep, DependencyAction,
+		DependencyEntry,
+	},
+};
 use cargo::{
 	core::{package::Package, Workspace},
 	GlobalContext,
 };
 use log::trace;
-use std::collections::HashMap;
-use toml_edit::{Item, Value};
+use regex::Regex;
+use std::{
+	collections::HashMap,
+	path::{Component, Path, PathBuf},
+};
+use toml_edit::{value, Array, DocumentMut, InlineTable, Item, Key, Table, Value};
 
 fn check_for_update(
 	name: String,
@@ -46,6 +61,7 @@ fn check_for_update(
 pub fn rename<M, P>(
 	gctx: &GlobalContext,
 	ws: &Workspace<'_>,
+	editor: &ManifestEditor,
 	predicate: P,
 	mapper: M,
 ) -> Result<(), anyhow::Error>
@@ -54,10 +70,10 @@ where
 	M: Fn(&Package) -> Option<String>,
 {
 	let updates = HashMap::<String, String>::from_iter(
-		edit_each(members_deep(gctx, ws).iter().filter(|p| predicate(p)), |p, doc| {
+		edit_each(editor, members_deep(gctx, ws).iter().filter(|p| predicate(p)), |p, doc| {
 			Ok(mapper(p).map(|new_name| {
 				gctx.shell()
-					.status("Renaming", format!("{:} -> {:}", dbg!(&p).name(), new_name))
+					.status("Renaming", format!("{:} -> {:}", p.name(), new_name))
 					.expect("Writing to the shell would have failed before. qed");
 				doc["package"]["name"] =
 					Item::Value(Value::from(new_name.to_string()).decorated(" ", ""));
@@ -74,28 +90,10 @@ where
 	}
 
 	gctx.shell().status("Updating", "Dependency tree")?;
-	edit_each(members_deep(gctx, ws).iter(), |p, doc| {
+	edit_each(editor, members_deep(gctx, ws).iter(), |p, doc| {
 		gctx.shell().status("Updating", p.name())?;
 		let root = doc.as_table_mut();
-		let mut updates_count = 0;
-		updates_count += edit_each_dep(root, |a, _, b, _| check_for_update(a, b, &updates));
-
-		if let Some(Item::Table(table)) = root.get_mut("target") {
-			let keys = Vec::from_iter(table.iter().filter_map(|(k, v)| {
-				if v.is_table() {
-					Some(k.to_owned())
-				} else {
-					None
-				}
-			}));
-
-			for k in keys {
-				if let Some(Item::Table(root)) = table.get_mut(&k) {
-					updates_count +=
-						edit_each_dep(root, |a, _, b, _| check_for_update(a, b, &updates));
-				}
-			}
-		}
+		let updates_count = edit_each_dep(root, |a, _, b, _| check_for_update(a, b, &updates));
 
 		if updates_count == 0 {
 			gctx.shell().status("Done", "No dependency updates")?;
@@ -110,3 +108,467 @@ where
 
 	Ok(())
 }
+
+/// A package renamed by [`rename_fully`]
+struct Renaming<'a> {
+	old_name: &'a str,
+	new_name: &'a str,
+	old_dir: PathBuf,
+	new_dir: PathBuf,
+}
+
+/// `path` with `.` and `..` resolved, without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+	let mut normalized = PathBuf::new();
+	for component in path.components() {
+		match component {
+			Component::CurDir => {},
+			Component::ParentDir => {
+				normalized.pop();
+			},
+			c => normalized.push(c),
+		}
+	}
+	normalized
+}
+
+/// The path of `to` relative to the directory `from`, with `/` as separator like in manifests
+fn relative(from: &Path, to: &Path) -> String {
+	let from = Vec::from_iter(normalize(from).components().map(|c| c.as_os_str().to_owned()));
+	let to = Vec::from_iter(normalize(to).components().map(|c| c.as_os_str().to_owned()));
+	let common = from.iter().zip(to.iter()).take_while(|(a, b)| a == b).count();
+	let parts = Vec::from_iter(
+		std::iter::repeat("..".to_owned())
+			.take(from.len() - common)
+			.chain(to[common..].iter().map(|c| c.to_string_lossy().into_owned())),
+	);
+	parts.join("/")
+}
+
+/// Replace the string `value` keeping its formatting
+fn replace_str(value: &mut Value, new: String) {
+	let decor = value.decor().clone();
+	*value = Value::from(new);
+	*value.decor_mut() = decor;
+}
+
+/// Rename the key `from` of `table` to `to`, keeping the order of the entries
+fn rename_key(table: &mut Table, from: &str, to: &str) {
+	let entries = Vec::from_iter(table.iter().map(|(key, _)| {
+		let (key, item) = table.get_key_value(key).expect("Iterating over the keys. qed");
+		(key.clone(), item.clone())
+	}));
+	table.clear();
+	for (key, item) in entries {
+		if key.get() == from {
+			let mut renamed = Key::new(to);
+			*renamed.leaf_decor_mut() = key.leaf_decor().clone();
+			table.insert_formatted(&renamed, item);
+		} else {
+			table.insert_formatted(&key, item);
+		}
+	}
+}
+
+/// Update the local dependencies in `deps` on the renamed package, of a manifest in `dir`.
+/// Returns whether the key of a dependency was renamed, rather than its `package`.
+fn update_dependencies(deps: &mut Table, dir: &Path, renaming: &Renaming) -> bool {
+	let mut renamed_key = false;
+	for (key, dep) in deps.iter_mut() {
+		let Some(info) = dep.as_table_like_mut() else {
+			continue; // `name = "1.0"` isn't local
+		};
+		if let Some(path) = info.get_mut("path").and_then(Item::as_value_mut) {
+			let points_to_old = path
+				.as_str()
+				.map(|p| normalize(&dir.join(p)) == renaming.old_dir)
+				.unwrap_or_default();
+			if points_to_old && renaming.old_dir != renaming.new_dir {
+				replace_str(path, relative(dir, &renaming.new_dir));
+			}
+		}
+		let is_local = info.contains_key("path") ||
+			info.get("workspace").and_then(Item::as_bool).unwrap_or_default();
+		let package = info.get("package").and_then(Item::as_str).unwrap_or(key.get()).to_owned();
+		// `package = "<new>"` was added by renaming without `--full` before
+		let refers = package == renaming.old_name ||
+			(package == renaming.new_name && key.get() == renaming.old_name);
+		if !is_local || !refers {
+			continue
+		}
+
+		if key.get() == renaming.old_name || key.get() == renaming.new_name {
+			renamed_key |= key.get() == renaming.old_name;
+			info.remove("package");
+		} else if let Some(package) = info.get_mut("package").and_then(Item::as_value_mut) {
+			replace_str(package, renaming.new_name.to_owned());
+		}
+	}
+	if renamed_key {
+		rename_key(deps, renaming.old_name, renaming.new_name);
+	}
+	renamed_key
+}
+
+/// Update references to the renamed dependency in `[features]`: `old/feat`, `old?/feat`,
+/// `dep:old` and the implicit feature `old` of an optional dependency.
+fn update_features(doc: &mut DocumentMut, renaming: &Renaming) {
+	let Some(features) = doc.get_mut("features").and_then(Item::as_table_like_mut) else { return };
+	let (old, new) = (renaming.old_name, renaming.new_name);
+	// a feature named like the dependency replaces the implicit one
+	let defines_old = features.contains_key(old);
+	for (_, values) in features.iter_mut() {
+		let Some(values) = values.as_array_mut() else { continue };
+		for value in values.iter_mut() {
+			let Some(feature) = value.as_str() else { continue };
+			let updated = if feature == format!("dep:{}", old) {
+				format!("dep:{}", new)
+			} else if let Some(rest) =
+				feature.strip_prefix(old).filter(|r| r.starts_with('/') || r.starts_with("?/"))
+			{
+				format!("{}{}", new, rest)
+			} else if feature == old && !defines_old {
+				new.to_owned()
+			} else {
+				continue
+			};
+			replace_str(value, updated);
+		}
+	}
+}
+
+/// Update the dependencies on the renamed package in the manifest in `dir`, returns whether the
+/// key of a dependency was renamed.
+fn update_references(doc: &mut DocumentMut, dir: &Path, renaming: &Renaming) -> bool {
+	let mut renamed_key = false;
+	for (_, deps) in dependency_tables(doc.as_table_mut()) {
+		renamed_key |= update_dependencies(deps, dir, renaming);
+	}
+	if let Some(deps) = doc
+		.get_mut("workspace")
+		.and_then(|w| w.get_mut("dependencies"))
+		.and_then(Item::as_table_mut)
+	{
+		update_dependencies(deps, dir, renaming);
+	}
+	if renamed_key {
+		update_features(doc, renaming);
+	}
+	renamed_key
+}
+
+/// Replace the crate `old` by `new` in paths like `old::item`, `use old;` and `extern crate old`
+fn rewrite_crate_paths(source: &str, old: &str, new: &str) -> String {
+	let old = regex::escape(old);
+	let paths = Regex::new(&format!(r"(?m)(^|[^\w:]){}(\s*::)", old))
+		.expect("The crate name is escaped. qed");
+	let items = Regex::new(&format!(r"\b(use|extern crate)(\s+){}\b", old))
+		.expect("The crate name is escaped. qed");
+	let source = paths.replace_all(source, format!("${{1}}{}${{2}}", new));
+	items.replace_all(&source, format!("${{1}}${{2}}{}", new)).into_owned()
+}
+
+/// The Rust sources of the package in `dir`, skipping `target` and nested packages
+fn rust_sources(dir: &Path, sources: &mut Vec<PathBuf>) -> Result<(), anyhow::Error> {
+	for entry in std::fs::read_dir(dir)? {
+		let path = entry?.path();
+		if path.is_dir() {
+			if path.file_name().map(|n| n != "target").unwrap_or_default() &&
+				!path.join("Cargo.toml").exists()
+			{
+				rust_sources(&path, sources)?;
+			}
+		} else if path.extension().map(|e| e == "rs").unwrap_or_default() {
+			sources.push(path);
+		}
+	}
+	Ok(())
+}
+
+/// The files of a crate named `old_name` in `dir`, re-exporting the library `lib` of the renamed
+/// package in its new directory, with the same version and forwarding its `features`. The
+/// renamed package is required by `requirement`.
+///
+/// Fields the renamed package inherits from the workspace, according to its `manifest`, are
+/// inherited by the facade as well.
+fn facade_files(
+	pkg: &Package,
+	manifest: &DocumentMut,
+	renaming: &Renaming,
+	dir: &Path,
+	lib: &str,
+	features: &[String],
+	requirement: String,
+) -> Vec<(PathBuf, String)> {
+	let (old, new) = (renaming.old_name, renaming.new_name);
+	let metadata = pkg.manifest().metadata();
+	let inherited = |key: &str| {
+		manifest
+			.get("package")
+			.and_then(|p| p.get(key))
+			.map(is_inherited)
+			.unwrap_or_default()
+	};
+
+	let mut package = Table::new();
+	package.insert("name", value(old));
+	// a version inherited by one of them only can't be bumped along with the other
+	for (key, field) in [
+		("version", Some(pkg.version().to_string())),
+		("edition", Some(pkg.manifest().edition().to_string())),
+		("license", metadata.license.clone()),
+		("repository", metadata.repository.clone()),
+	] {
+		if inherited(key) {
+			package.insert(key, inheritance());
+		} else if let Some(field) = field {
+			package.insert(key, value(field));
+		}
+	}
+	package.insert("description", value(format!("Deprecated, renamed to `{}`", new)));
+	package.insert("readme", value("README.md"));
+
+	let mut dependency = InlineTable::new();
+	dependency.insert("version", Value::from(requirement));
+	dependency.insert("path", Value::from(relative(dir, &renaming.new_dir)));
+	if features.iter().any(|f| f == "default") {
+		dependency.insert("default-features", Value::from(false));
+	}
+	dependency.fmt();
+	let mut dependencies = Table::new();
+	dependencies.insert(new, Item::Value(Value::InlineTable(dependency)));
+
+	let mut doc = DocumentMut::new();
+	doc.insert("package", Item::Table(package));
+	doc.insert("dependencies", Item::Table(dependencies));
+	if !features.is_empty() {
+		let mut forwarded = Table::new();
+		for feature in features {
+			let forward = Array::from_iter([format!("{}/{}", new, feature)]);
+			forwarded.insert(feature, value(forward));
+		}
+		doc.insert("features", Item::Table(forwarded));
+	}
+
+	let link = format!("[`{}`](https://crates.io/crates/{})", new, new);
+	let lib = format!(
+		"//! Deprecated: `{}` was renamed to {}, this crate only re-exports it. Depend on `{}`\n\
+		//! instead.\n\npub use {}::*;\n",
+		old, link, new, lib
+	);
+	let readme = format!(
+		"# {}\n\n**Deprecated**: `{}` was renamed to {}. This crate only re-exports it to keep \
+		existing users building, please depend on `{}` instead.\n",
+		old, old, link, new
+	);
+	vec![
+		(dir.join("Cargo.toml"), doc.to_string()),
+		(dir.join("src").join("lib.rs"), lib),
+		(dir.join("README.md"), readme),
+	]
+}
+
+/// Add the facade in `dir` to the workspace `members`, unless a glob covers it already, and to a
+/// version group with the renamed package, so both are always released together.
+fn register_facade(
+	doc: &mut DocumentMut,
+	ws: &Workspace<'_>,
+	renaming: &Renaming,
+	dir: &Path,
+) -> Result<(), anyhow::Error> {
+	let member = relative(ws.root(), dir);
+	let path = |keys: &[&str]| Vec::from_iter(keys.iter().map(|k| k.to_string()));
+	let workspace = parent_table(doc.as_item_mut(), &path(&["workspace"]))?;
+	let members = workspace
+		.entry("members")
+		.or_insert_with(|| value(Array::new()))
+		.as_array_mut()
+		.ok_or_else(|| anyhow::anyhow!("`workspace.members` isn't an array"))?;
+	let mut covered = false;
+	for pattern in members.iter().filter_map(Value::as_str) {
+		covered |= globset::Glob::new(pattern)?.compile_matcher().is_match(&member);
+	}
+	if !covered {
+		members.push(member);
+	}
+
+	let groups = parent_table(
+		doc.as_item_mut(),
+		&path(&["workspace", "metadata", "dragons", "version-groups"]),
+	)?;
+	let group = Array::from_iter([renaming.new_name, renaming.old_name]);
+	groups.insert(renaming.old_name, value(group));
+	Ok(())
+}
+
+/// Rename the package `old_name` to `new_name` completely: dependents use the new name as their
+/// dependency key instead of an alias, including in their `[features]` and Rust sources, and
+/// `[workspace.dependencies]` is updated. With `move_dir`, the directory of the package is renamed
+/// as well, updating the `members` of the workspace and the `path` of its dependents.
+///
+/// With `keep_facade`, a crate named `old_name` re-exporting the renamed package is created, in
+/// the old directory if it's moved or next to it otherwise, which shares a version group with the
+/// renamed package and is thus released with it.
+///
+/// The directory is moved when the editor commits, before the edited files are written to it.
+pub fn rename_fully(
+	gctx: &GlobalContext,
+	ws: &Workspace<'_>,
+	editor: &ManifestEditor,
+	old_name: &str,
+	new_name: &str,
+	move_dir: bool,
+	keep_facade: bool,
+) -> Result<(), anyhow::Error> {
+	let pkg = ws
+		.members()
+		.find(|p| p.name().as_str() == old_name)
+		.ok_or_else(|| anyhow::anyhow!("No member named `{}`", old_name))?;
+	if ws.members().any(|p| p.name().as_str() == new_name) {
+		anyhow::bail!("Aborting: there already is a member named `{}`", new_name);
+	}
+	let old_dir = normalize(pkg.root());
+	let new_dir = if move_dir { old_dir.with_file_name(new_name) } else { old_dir.clone() };
+	if move_dir && (old_dir == normalize(ws.root()) || new_dir.exists()) {
+		anyhow::bail!("Aborting: can't move {} to {}", old_dir.display(), new_dir.display());
+	}
+	let facade_dir = if move_dir {
+		old_dir.clone()
+	} else {
+		old_dir.with_file_name(format!("{}-facade", old_name))
+	};
+	if keep_facade && !move_dir && facade_dir.exists() {
+		anyhow::bail!("Aborting: can't create the facade in {}", facade_dir.display());
+	}
+	let renaming = Renaming { old_name, new_name, old_dir, new_dir };
+	// read before the package is moved
+	let doc: DocumentMut = editor.read(pkg.manifest_path())?.parse()?;
+	let features = doc
+		.get("features")
+		.and_then(Item::as_table_like)
+		.map(|features| Vec::from_iter(features.iter().map(|(name, _)| name.to_owned())))
+		.unwrap_or_default();
+	let lib = pkg.targets().iter().find(|t| t.is_lib()).map(|t| t.crate_name());
+	if keep_facade && lib.is_none() {
+		anyhow::bail!("Aborting: {} has no library the facade could re-export", old_name);
+	}
+
+	gctx.shell().status("Renaming", format!("{} -> {}", old_name, new_name))?;
+	let (old_crate, new_crate) = (old_name.replace('-', "_"), new_name.replace('-', "_"));
+	// sources refer to an explicit `[lib] name` instead, which is only kept if it differs
+	let crate_renamed = edit_each(editor, std::iter::once(pkg), |_, doc| {
+		doc["package"]["name"] = Item::Value(Value::from(new_name).decorated(" ", ""));
+		let Some(lib) = doc.get_mut("lib").and_then(Item::as_table_like_mut) else {
+			return Ok(true)
+		};
+		match lib.get_mut("name").and_then(Item::as_value_mut) {
+			Some(name) if name.as_str() == Some(old_crate.as_str()) => {
+				replace_str(name, new_crate.clone());
+				Ok(true)
+			},
+			Some(_) => Ok(false),
+			None => Ok(true),
+		}
+	})?
+	.into_iter()
+	.all(|renamed| renamed);
+
+	let mut dirs = Vec::from_iter(
+		edit_each(editor, ws.members(), |p, doc| {
+			let renamed_key = update_references(doc, p.root(), &renaming);
+			if renamed_key {
+				gctx.shell().status("Updating", p.name())?;
+			}
+			Ok(renamed_key.then(|| p.root().to_path_buf()))
+		})?
+		.into_iter()
+		.flatten(),
+	);
+	edit_root(editor, ws, |doc| {
+		update_references(doc, ws.root(), &renaming);
+		let Some(workspace) = doc.get_mut("workspace").and_then(Item::as_table_like_mut) else {
+			return Ok(());
+		};
+		for key in ["members", "default-members"] {
+			let Some(members) = workspace.get_mut(key).and_then(Item::as_array_mut) else {
+				continue
+			};
+			for member in members.iter_mut() {
+				let is_old = member
+					.as_str()
+					.map(|m| normalize(&ws.root().join(m)) == renaming.old_dir)
+					.unwrap_or_default();
+				if is_old && renaming.old_dir != renaming.new_dir {
+					replace_str(member, relative(ws.root(), &renaming.new_dir));
+				}
+			}
+		}
+		Ok(())
+	})?;
+
+	if crate_renamed {
+		dirs.push(pkg.root().to_path_buf());
+		for dir in dirs {
+			let mut sources = Vec::new();
+			rust_sources(&dir, &mut sources)?;
+			for source in sources {
+				let content = editor.read(&source)?;
+				let rewritten = rewrite_crate_paths(&content, &old_crate, &new_crate);
+				if rewritten != content {
+					editor.write(&source, rewritten)?;
+				}
+			}
+		}
+	}
+
+	if move_dir {
+		gctx.shell().status(
+			"Moving",
+			format!("{} to {}", renaming.old_dir.display(), renaming.new_dir.display()),
+		)?;
+		// the edits staged for the package so far move along
+		editor.move_dir(&renaming.old_dir, &renaming.new_dir)?;
+	}
+
+	if keep_facade {
+		gctx.shell()
+			.status("Creating", format!("facade {} in {}", old_name, facade_dir.display()))?;
+		// an explicit `[lib] name` differing from the package name is kept
+		let lib = if crate_renamed { new_crate } else { lib.expect("Checked above. qed") };
+		let requirement = workspace_config(ws)?
+			.requirement_style
+			.unwrap_or_default()
+			.requirement(pkg.version());
+		for (path, content) in
+			facade_files(pkg, &doc, &renaming, &facade_dir, &lib, &features, requirement)
+		{
+			editor.write(&path, content)?;
+		}
+		edit_root(editor, ws, |doc| register_facade(doc, ws, &renaming, &facade_dir))?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn crate_paths() {
+		let source =
+			"use old_name::Thing;\nextern crate old_name as on;\nfn f() -> old_name::Thing {\n\
+			\told_name::make()\n}\nmod inner { use crate::old_name::X; use old_names::Y; }\n";
+		assert_eq!(
+			rewrite_crate_paths(source, "old_name", "new_name"),
+			"use new_name::Thing;\nextern crate new_name as on;\nfn f() -> new_name::Thing {\n\
+			\tnew_name::make()\n}\nmod inner { use crate::old_name::X; use old_names::Y; }\n"
+		);
+	}
+
+	#[test]
+	fn relative_paths() {
+		assert_eq!(relative(Path::new("/ws"), Path::new("/ws/crates/b")), "crates/b");
+		assert_eq!(relative(Path::new("/ws/app"), Path::new("/ws/crates/b")), "../crates/b");
+		assert_eq!(normalize(Path::new("/ws/app/../crates/./a")), Path::new("/ws/crates/a"));
+	}
+}
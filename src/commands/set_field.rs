@@ -1,34 +1,223 @@
-use crate::util::edit_each;
+use crate::{editor::ManifestEditor, template::expand, util::edit_each};
 use cargo::core::package::Package;
+use std::path::Path;
 
-use toml_edit::{Item, Table, Value};
+use toml_edit::{Array, InlineTable, Item, Table, Value};
 
-/// Deactivate the Dev Dependencies Section of the given toml
+/// The keys leading to the field `name`, within the table `root_key` if given.
+///
+/// `name` is a dotted path like `metadata.docs.rs.all-features`, keys containing dots can be
+/// quoted. Without `root_key`, a plain name is looked up in `[package]` and a dotted path starts
+/// at the root of the manifest.
+pub fn field_path(root_key: Option<&str>, name: &str) -> Result<Vec<String>, anyhow::Error> {
+	let mut keys = Vec::new();
+	let mut rest = name.trim();
+	while !rest.is_empty() {
+		let (key, tail) = match rest.chars().next() {
+			Some(quote @ ('"' | '\'')) => {
+				let end = rest[1..]
+					.find(quote)
+					.ok_or_else(|| anyhow::anyhow!("Unclosed quote in `{}`", name))?;
+				(&rest[1..end + 1], rest[end + 2..].trim_start())
+			},
+			_ => {
+				let end = rest.find('.').unwrap_or(rest.len());
+				(rest[..end].trim(), &rest[end..])
+			},
+		};
+		if key.is_empty() {
+			anyhow::bail!("Empty key in `{}`", name);
+		}
+		keys.push(key.to_owned());
+		rest = match tail.strip_prefix('.') {
+			Some(tail) if !tail.trim().is_empty() => tail.trim_start(),
+			Some(_) => anyhow::bail!("Empty key in `{}`", name),
+			None if tail.is_empty() => tail,
+			None => anyhow::bail!("Expected `.` after `{}` in `{}`", key, name),
+		};
+	}
+	if keys.is_empty() {
+		anyhow::bail!("No field given");
+	}
+	match root_key {
+		Some(root_key) => keys.insert(0, root_key.to_owned()),
+		None if keys.len() == 1 => keys.insert(0, "package".to_owned()),
+		None => {},
+	}
+	Ok(keys)
+}
+
+/// Fill in `{name}`, `{version}`, `{path}` (relative to the workspace `root`), `{dir}` and
+/// `{description}` of `pkg`.
+fn expand_package(template: &str, pkg: &Package, root: &Path) -> Result<String, anyhow::Error> {
+	expand(template, |name| {
+		let value = match name {
+			"name" => pkg.name().to_string(),
+			"version" => pkg.version().to_string(),
+			"path" => {
+				let path = pkg.root().strip_prefix(root).unwrap_or(pkg.root());
+				Vec::from_iter(path.components().map(|c| c.as_os_str().to_string_lossy())).join("/")
+			},
+			"dir" => pkg
+				.root()
+				.file_name()
+				.map(|d| d.to_string_lossy().into_owned())
+				.unwrap_or_default(),
+			"description" => pkg
+				.manifest()
+				.metadata()
+				.description
+				.clone()
+				.ok_or_else(|| anyhow::anyhow!("{} has no description", pkg.name()))?,
+			_ => return Ok(None),
+		};
+		Ok(Some(value))
+	})
+}
+
+/// Parse `raw` as a TOML value, e.g. `true`, `42`, `["a", "b"]` or `{ workspace = true }`,
+/// falling back to a plain string. A field holding a string keeps being a string.
+fn parse_value(raw: &str, existing: Option<&Item>) -> Value {
+	let keep_string = existing.and_then(Item::as_str).is_some();
+	match raw.parse::<Value>() {
+		Ok(value @ (Value::String(_) | Value::Array(_) | Value::InlineTable(_))) => value,
+		Ok(value @ (Value::Integer(_) | Value::Boolean(_))) if !keep_string => value,
+		_ => Value::from(raw),
+	}
+}
+
+/// The table holding the last key of `path`, adding missing tables on the way
+pub fn parent_table<'t>(
+	item: &'t mut Item,
+	path: &[String],
+) -> Result<&'t mut dyn toml_edit::TableLike, anyhow::Error> {
+	let mut item = item;
+	for key in path {
+		let inline = item.is_inline_table();
+		let table = item
+			.as_table_like_mut()
+			.ok_or_else(|| anyhow::anyhow!("`{}` is not within a table", key))?;
+		item = table.entry(key).or_insert_with(|| {
+			if inline {
+				Item::Value(Value::InlineTable(InlineTable::new()))
+			} else {
+				let mut table = Table::new();
+				table.set_implicit(true);
+				Item::Table(table)
+			}
+		});
+	}
+	item.as_table_like_mut()
+		.ok_or_else(|| anyhow::anyhow!("`{}` is not a table", path.join(".")))
+}
+
+/// Set the field at `path` to `value` in all given manifests, adding it and the tables leading to
+/// it if missing. With `append`, the value is added to the array at `path` instead.
+///
+/// Placeholders in `value` are filled in for each package, see [`expand_package`].
 pub fn set_field<'a, I>(
+	editor: &ManifestEditor,
+	root: &Path,
 	iter: I,
-	root_key: String,
-	key: String,
-	value: Value,
+	path: &[String],
+	value: &str,
+	append: bool,
 ) -> Result<(), anyhow::Error>
 where
 	I: Iterator<Item = &'a Package>,
 {
-	let _ = edit_each(iter, |p, doc| {
-		let table = {
-			let t =
-				doc.as_table_mut().entry(&root_key).or_insert_with(|| Item::Table(Table::new()));
-			if let Item::Table(inner) = t {
-				inner
-			} else {
-				anyhow::bail!(
-					"Error in manifest of {:}: root key {:} is not a table.",
-					p.name(),
-					root_key
-				);
-			}
+	let (key, parents) = path.split_last().expect("Field paths are never empty. qed");
+	edit_each(editor, iter, |p, doc| {
+		let table = parent_table(doc.as_item_mut(), parents)
+			.map_err(|e| anyhow::anyhow!("Error in manifest of {:}: {:}", p.name(), e))?;
+		let value = expand_package(value, p, root)?;
+		let value = parse_value(&value, table.get(key));
+		if !append {
+			table.insert(key, Item::Value(value.decorated(" ", "")));
+			return Ok(())
+		}
+
+		let values = match value {
+			Value::Array(values) => Vec::from_iter(values),
+			value => vec![value],
 		};
-		let _ = table.insert(&key, Item::Value(value.clone().decorated(" ", "")));
+		let item = table
+			.entry(key)
+			.or_insert_with(|| Item::Value(Value::Array(Array::new()).decorated(" ", "")));
+		let Some(array) = item.as_array_mut() else {
+			anyhow::bail!(
+				"Error in manifest of {:}: `{}` is not an array",
+				p.name(),
+				path.join(".")
+			)
+		};
+		for mut value in values {
+			value.decor_mut().clear();
+			let repr = value.to_string();
+			if !array.iter().any(|v| v.to_string().trim() == repr) {
+				array.push(value);
+			}
+		}
+		Ok(())
+	})?;
+	Ok(())
+}
+
+/// Remove the field at `path` from all given manifests, if present
+pub fn unset_field<'a, I>(
+	editor: &ManifestEditor,
+	iter: I,
+	path: &[String],
+) -> Result<(), anyhow::Error>
+where
+	I: Iterator<Item = &'a Package>,
+{
+	let (key, parents) = path.split_last().expect("Field paths are never empty. qed");
+	edit_each(editor, iter, |_, doc| {
+		let mut item = doc.as_item_mut();
+		for parent in parents {
+			match item.get_mut(parent.as_str()) {
+				Some(inner) => item = inner,
+				None => return Ok(()),
+			}
+		}
+		if let Some(table) = item.as_table_like_mut() {
+			table.remove(key);
+		}
 		Ok(())
-	});
+	})?;
 	Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn paths() {
+		assert_eq!(field_path(None, "version").unwrap(), vec!["package", "version"]);
+		assert_eq!(field_path(Some("lib"), "name").unwrap(), vec!["lib", "name"]);
+		assert_eq!(
+			field_path(None, "package.metadata.docs.rs.all-features").unwrap(),
+			vec!["package", "metadata", "docs", "rs", "all-features"]
+		);
+		assert_eq!(
+			field_path(None, r#"package.metadata."docs.rs""#).unwrap(),
+			vec!["package", "metadata", "docs.rs"]
+		);
+		assert!(field_path(None, "package..version").is_err());
+		assert!(field_path(None, "package.'metadata").is_err());
+		assert!(field_path(None, "").is_err());
+	}
+
+	#[test]
+	fn values() {
+		assert_eq!(parse_value("1.0.0", None).as_str(), Some("1.0.0"));
+		assert_eq!(parse_value("42", None).as_integer(), Some(42));
+		assert_eq!(parse_value("2021", Some(&Item::Value("2018".into()))).as_str(), Some("2021"));
+		assert_eq!(parse_value("true", None).as_bool(), Some(true));
+		assert_eq!(parse_value(r#"["a", "b"]"#, None).as_array().map(|a| a.len()), Some(2));
+		assert!(parse_value("{ workspace = true }", None).is_inline_table());
+		assert_eq!(parse_value("1.70", None).as_str(), Some("1.70"));
+	}
+}
@@ -0,0 +1,207 @@
+use crate::{
+	registry::{published_versions, registry_state, RegistryState},
+	util::{members_deep, ReportFormat},
+};
+use cargo::{
+	core::{dependency::DepKind, package::Package, Workspace},
+	GlobalContext,
+};
+use semver::Version;
+use serde::Serialize;
+use std::{collections::HashSet, path::PathBuf};
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct StatusRow {
+	name: String,
+	local: Version,
+	/// Latest published release
+	published: Option<Version>,
+	/// Latest published pre-release
+	published_pre: Option<Version>,
+	/// Whether `local` is published already
+	is_published: bool,
+	/// Whether `local` is lower than any published release, or pre-release if `local` is one
+	regression: bool,
+	/// Files differing from the published `.crate` of the same version
+	changed_files: Vec<PathBuf>,
+	/// Why the package couldn't be compared with the published `.crate`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	error: Option<String>,
+	/// Members depending on an unpublished `local`, which need updates once it's released
+	dependents: Vec<String>,
+}
+
+/// Whether `local` is lower than a published version. Pre-releases only count for local
+/// pre-releases, a release may well be lower than a pre-release of the next version.
+fn is_regression(local: &Version, published: &[Version]) -> bool {
+	published
+		.iter()
+		.any(|v| (v.pre.is_empty() || !local.pre.is_empty()) && v > local)
+}
+
+impl StatusRow {
+	fn state(&self) -> String {
+		if let Some(error) = &self.error {
+			format!("error, {}", error)
+		} else if self.regression {
+			let pre = self.published_pre.iter().filter(|_| !self.local.pre.is_empty());
+			format!(
+				"regression, {} is published",
+				self.published
+					.iter()
+					.chain(pre)
+					.max()
+					.expect("A regression is lower than a published version. qed")
+			)
+		} else if !self.is_published {
+			"not published".to_owned()
+		} else if !self.changed_files.is_empty() {
+			format!("changed, not bumped ({} files differ)", self.changed_files.len())
+		} else {
+			"published".to_owned()
+		}
+	}
+}
+
+/// Report for every selected package its local and published versions, whether it changed
+/// compared to the published version and which dependents need updates once it's released.
+pub fn status<P>(
+	gctx: &GlobalContext,
+	ws: &Workspace<'_>,
+	predicate: P,
+	registry_cache: &[PathBuf],
+	format: ReportFormat,
+	check_only: bool,
+) -> Result<(), anyhow::Error>
+where
+	P: Fn(&Package) -> bool,
+{
+	let mut seen = HashSet::new();
+	let members =
+		Vec::from_iter(members_deep(gctx, ws).into_iter().filter(|p| seen.insert(p.name())));
+	let selected = Vec::from_iter(members.iter().filter(|p| predicate(p)));
+	let published = published_versions(gctx, selected.iter().map(|p| p.name()))?;
+
+	gctx.shell().status("Comparing", "Packages with published versions")?;
+
+	let mut rows = Vec::new();
+	for pkg in selected {
+		let versions = published.get(&pkg.name()).cloned().unwrap_or_default();
+		let is_published = versions.contains(pkg.version());
+		let (changed_files, error) = match registry_state(gctx, ws, pkg, registry_cache) {
+			Ok(RegistryState::Changed(files)) => (files, None),
+			Ok(RegistryState::Unchanged | RegistryState::Unknown) => (Vec::new(), None),
+			Err(e) => {
+				gctx.shell().warn(format!("Can't compare {}: {:?}", pkg.name(), e))?;
+				(Vec::new(), Some(format!("{:#}", e)))
+			},
+		};
+		let dependents = if is_published {
+			Vec::new()
+		} else {
+			Vec::from_iter(
+				members
+					.iter()
+					.filter(|m| {
+						m.dependencies().iter().any(|d| {
+							d.package_name() == pkg.name() &&
+								d.source_id().is_path() && d.kind() != DepKind::Development
+						})
+					})
+					.map(|m| m.name().to_string()),
+			)
+		};
+		rows.push(StatusRow {
+			name: pkg.name().to_string(),
+			local: pkg.version().clone(),
+			published: versions.iter().filter(|v| v.pre.is_empty()).max().cloned(),
+			published_pre: versions.iter().filter(|v| !v.pre.is_empty()).max().cloned(),
+			is_published,
+			regression: is_regression(pkg.version(), &versions),
+			changed_files,
+			error,
+			dependents,
+		});
+	}
+
+	match format {
+		ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+		ReportFormat::Table => {
+			let version = |v: &Option<Version>| {
+				v.as_ref().map(|v| v.to_string()).unwrap_or_else(|| "-".to_owned())
+			};
+			let table = Vec::from_iter(rows.iter().map(|r| {
+				[
+					r.name.clone(),
+					r.local.to_string(),
+					version(&r.published),
+					version(&r.published_pre),
+					r.state(),
+					r.dependents.join(", "),
+				]
+			}));
+			let header = ["name", "local", "published", "pre-release", "state", "dependents"]
+				.map(|h| h.to_owned());
+			let mut widths = header.clone().map(|h| h.len());
+			for row in table.iter() {
+				for (width, cell) in widths.iter_mut().zip(row.iter()) {
+					*width = (*width).max(cell.len());
+				}
+			}
+			for row in std::iter::once(&header).chain(table.iter()) {
+				let cells = Vec::from_iter(
+					row.iter().zip(widths.iter()).map(|(cell, &width)| format!("{cell:width$}")),
+				);
+				println!("{}", cells.join("  ").trim_end());
+			}
+		},
+	}
+
+	let unbumped = rows.iter().filter(|r| !r.changed_files.is_empty()).count();
+	let regressions = rows.iter().filter(|r| r.regression).count();
+	let errors = rows.iter().filter(|r| r.error.is_some()).count();
+	if check_only && unbumped + regressions + errors > 0 {
+		anyhow::bail!(
+			"Aborting: {} packages changed without a version bump, {} are lower than published, {} couldn't be compared.",
+			unbumped,
+			regressions,
+			errors
+		)
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn row(local: &str, published: &[&str]) -> StatusRow {
+		let local = Version::parse(local).unwrap();
+		let versions = Vec::from_iter(published.iter().map(|v| Version::parse(v).unwrap()));
+		StatusRow {
+			name: "a".to_owned(),
+			published: versions.iter().filter(|v| v.pre.is_empty()).max().cloned(),
+			published_pre: versions.iter().filter(|v| !v.pre.is_empty()).max().cloned(),
+			is_published: versions.contains(&local),
+			regression: is_regression(&local, &versions),
+			local,
+			changed_files: Vec::new(),
+			error: None,
+			dependents: Vec::new(),
+		}
+	}
+
+	#[test]
+	fn states() {
+		assert_eq!(row("1.0.0", &["0.9.0", "1.0.0"]).state(), "published");
+		assert_eq!(row("1.1.0-dev", &["1.0.0"]).state(), "not published");
+		// pre-releases of the next version don't make a release a regression
+		assert_eq!(row("1.0.0", &["1.0.0", "1.1.0-rc.1"]).state(), "published");
+		assert_eq!(
+			row("1.1.0-dev", &["1.0.0", "1.1.0-rc.1"]).state(),
+			"regression, 1.1.0-rc.1 is published"
+		);
+		assert_eq!(row("0.9.1", &["1.0.0"]).state(), "regression, 1.0.0 is published");
+	}
+}
@@ -1,10 +1,6 @@
-use crate::util::members_deep;
+use crate::{registry::published_versions, util::members_deep};
 use cargo::{
-	core::{package::Package, Dependency, SourceId, Workspace},
-	sources::{
-		registry::RegistrySource,
-		source::{QueryKind, Source},
-	},
+	core::{package::Package, SourceId, Workspace},
 	util::interning::InternedString,
 	GlobalContext,
 };
@@ -76,36 +72,15 @@ where
 
 	let ignored = HashSet::<InternedString>::from_iter(to_ignore.into_iter().map(|m| m.name()));
 
-	gctx.shell()
-		.status("Syncing", "Versions from crates.io")
-		.expect("Writing to Shell doesn't fail");
-
-	let mut already_published = HashSet::new();
-	let mut registry = RegistrySource::remote(
-		SourceId::crates_io(gctx).expect(
-			"Your main registry (usually crates.io) can't be read. Please check your .cargo/config",
-		),
-		&Default::default(),
-		gctx,
-	)
-	.expect("Failed getting remote registry");
-	let lock = gctx.acquire_package_cache_lock(cargo::util::cache_lock::CacheLockMode::Shared)?;
-
-	registry.invalidate_cache();
-
-	for m in members.iter() {
-		let dep = Dependency::parse(m.name(), Some(&m.version().to_string()), registry.source_id())
-			.expect("Parsing our dependency doesn't fail");
-
-		let _ = registry
-			.query(&dep, QueryKind::Exact, &mut |_| {
-				already_published.insert(m.name());
+	let published = published_versions(gctx, members.iter().map(|m| m.name()))?;
+	let already_published = HashSet::<InternedString>::from_iter(
+		members
+			.iter()
+			.filter(|m| {
+				published.get(&m.name()).map(|v| v.contains(m.version())).unwrap_or_default()
 			})
-			.map(|e| e.expect("Quering the local registry doesn't fail"));
-	}
-
-	// drop the global package lock
-	drop(lock);
+			.map(|m| m.name()),
+	);
 
 	let map =
 		HashMap::<InternedString, NodeIndex>::from_iter(members.iter().filter_map(|&member| {
@@ -225,27 +200,16 @@ fn graphviz<'i, I: IntoIterator<Item = &'i Vec<NodeIndex>>, W: Write>(
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use cargo::{
-		core::{manifest::Manifest, *},
-		util::toml::TomlManifest,
-		Config,
-	};
+	use cargo_util_schemas::manifest::TomlManifest;
 
 	use anyhow::Result;
 	use itertools::Itertools;
 	use semver::Version;
 	use std::path::Path;
 
-	/// Test helper to create a `struct Manifest`
+	/// Test helper to create the content of a `Cargo.toml`
 	/// that is only living in memory, but could be written to disk.
-	fn make_manifest(
-		config: &Config,
-		base: &std::path::Path,
-		name: &'static str,
-		version: Version,
-		source_id: SourceId,
-		dependencies: impl AsRef<[Dependency]>,
-	) -> Manifest {
+	fn make_manifest(name: &str, version: &Version, dependencies: &[(&str, &str)]) -> String {
 		let toml_manifest = format!(
 			r###"
 [package]
@@ -261,54 +225,35 @@ publish = false
 			version = version
 		);
 
-		let toml_manifest =
-			dependencies.as_ref().iter().fold(toml_manifest, |toml_manifest, dep| {
-				toml_manifest +
-					format!(
-						r###"
+		dependencies.iter().fold(toml_manifest, |toml_manifest, (dep, version_req)| {
+			toml_manifest +
+				format!(
+					r###"
 {name} = "{version}""###,
-						name = dep.package_name(),
-						version = dep.version_req()
-					)
-					.as_str()
-			});
-
-		let toml_manifest = toml_manifest.as_str();
-		let toml_manifest: TomlManifest = toml::from_str(toml_manifest).unwrap();
-		let (manifest, _paths) = TomlManifest::to_real_manifest(
-			&std::rc::Rc::new(toml_manifest),
-			false,
-			source_id,
-			base,
-			config,
-		)
-		.unwrap();
-
-		manifest
+					name = dep,
+					version = version_req
+				)
+				.as_str()
+		})
 	}
 
 	#[test]
 	fn mock_make_manifest_works() {
-		let cfg = Config::default().unwrap();
-		let dir =
-			tempdir::TempDir::new("mock_make_manifest_works").expect("Creating temp dir works");
-		let _ = dbg!(make_manifest(
-			&cfg,
-			dir.path(),
+		let manifest = make_manifest(
 			"dinodinodino",
-			Version::parse("1.2.3").unwrap(),
-			SourceId::crates_io(&cfg).unwrap(),
-			&[],
-		));
+			&Version::parse("1.2.3").unwrap(),
+			&[("closing", "1.6.4")],
+		);
+		let manifest: TomlManifest = toml::from_str(&manifest).unwrap();
+		assert_eq!(manifest.package().unwrap().name.as_str(), "dinodinodino");
+		assert!(manifest.dependencies.unwrap().contains_key("closing"));
 	}
 
-	use cargo::core::VirtualManifest;
-
 	#[derive(Default, Debug, Clone)]
 	struct Krate {
 		name: &'static str,
 		version: Option<Version>,
-		dependencies: Vec<Dependency>,
+		dependencies: Vec<(&'static str, &'static str)>,
 	}
 
 	impl Krate {
@@ -322,12 +267,8 @@ publish = false
 			dependency: &'static str,
 			version_req: &'static str,
 		) -> Result<&mut Self> {
-			// TODO make this pretty
-			let config = Config::default().unwrap();
-			let source_id = SourceId::crates_io(&config)?;
-
-			let dependency = Dependency::parse(dependency, version_req.into(), source_id)?;
-			self.dependencies.push(dependency);
+			semver::VersionReq::parse(version_req)?;
+			self.dependencies.push((dependency, version_req));
 			Ok(self)
 		}
 	}
@@ -345,42 +286,12 @@ publish = false
 		}
 
 		pub fn build(self, base: impl AsRef<Path>) -> Result<Workspace<'static>> {
-			let config = {
-				let config = Config::default().unwrap();
-				Box::leak(Box::new(config))
+			let gctx = {
+				let gctx = GlobalContext::default().unwrap();
+				Box::leak(Box::new(gctx))
 			};
 			let base = base.as_ref();
 
-			let source_id = SourceId::crates_io(&*config).unwrap();
-
-			let manifests = self
-				.krates
-				.iter()
-				.map(|Krate { name, version, dependencies }| {
-					Ok(make_manifest(
-						config,
-						base,
-						name,
-						version.clone().expect("Must have version. qed"),
-						source_id,
-						dependencies,
-					))
-				})
-				.collect::<Result<Vec<Manifest>>>()?;
-
-			let root_config = WorkspaceRootConfig::new(
-				base,
-				&Some(
-					manifests.iter().map(|manifest| manifest.name().as_str().to_owned()).collect(),
-				),
-				&None,
-				&Some(Vec::new()),
-				&None,
-				&None,
-			);
-
-			let vconfig = WorkspaceConfig::Root(root_config);
-
 			// crate the filesystem tree
 			{
 				std::fs::create_dir_all(base).unwrap();
@@ -392,20 +303,18 @@ publish = false
     ]
     "###,
 					String::from_iter(Itertools::intersperse(
-						manifests
-							.iter()
-							.map(|manifest| format!(r#""./{}""#, manifest.name().as_str())),
+						self.krates.iter().map(|krate| format!(r#""./{}""#, krate.name)),
 						", ".to_owned()
 					))
 				);
 				std::fs::write(base.join("Cargo.toml"), content.as_bytes()).unwrap();
-				for manifest in manifests.iter() {
-					let name = manifest.name().as_str();
+				for Krate { name, version, dependencies } in self.krates.iter() {
 					let manifest_path = base.join(name);
+					let version = version.clone().expect("Must have version. qed");
 					std::fs::create_dir_all(manifest_path.join("src")).unwrap();
 					std::fs::write(
 						manifest_path.join("Cargo.toml"),
-						toml::to_string(manifest.original()).unwrap().as_str().as_bytes(),
+						make_manifest(name, &version, dependencies).as_bytes(),
 					)
 					.unwrap();
 					std::fs::write(
@@ -423,21 +332,7 @@ publish = false
 				}
 			}
 
-			let vmanifest = VirtualManifest::new(
-				Vec::new(),
-				HashMap::default(),
-				vconfig,
-				None,
-				Features::default(),
-				None,
-			);
-
-			let ws = Workspace::new_virtual(
-				base.to_path_buf(),
-				base.join("Cargo.toml"),
-				vmanifest,
-				&*config,
-			)?;
+			let ws = Workspace::new(&base.join("Cargo.toml"), &*gctx)?;
 			Ok(ws)
 		}
 	}
@@ -480,8 +375,9 @@ publish = false
 		wsb.add_crate("closing").version(1, 6, 9);
 
 		let ws = wsb.build(&tmp)?;
-		let to_release = packages_to_release(&ws, |_pkg| true, tmp.path().join("diamond.dot"))
-			.expect("There are no cycles in a diamond shaped, directed, dependency graph. qed");
+		let to_release =
+			packages_to_release(ws.gctx(), &ws, |_pkg| true, tmp.path().join("diamond.dot"))
+				.expect("There are no cycles in a diamond shaped, directed, dependency graph. qed");
 		// must be in release order, so the leaf has to have a lower index, dependencies on the same
 		// level are ordered by there reverse appearance in the members declaration
 		assert_eq!(
@@ -502,7 +398,7 @@ publish = false
 
 		let ws = wsb.build(&tmp)?;
 		let ErrorWithCycles(cycles, _err) =
-			packages_to_release_inner(&ws, |_pkg| true, tmp.path().join("circular.dot"))
+			packages_to_release_inner(ws.gctx(), &ws, |_pkg| true, tmp.path().join("circular.dot"))
 				.unwrap_err();
 		assert_eq!(cycles.len(), 1);
 		assert_eq!(cycles[0].len(), 3);
@@ -0,0 +1,69 @@
+use crate::editor::{journals, ManifestEditor};
+use cargo::{core::Workspace, GlobalContext};
+
+/// Restore the manifests a previous run changed and move back the directories it moved, the
+/// latest run unless `id` is given.
+///
+/// Refuses if any of them was changed since that run, unless `force` is set. On dry runs the
+/// original content is staged in the editor, to show what would be restored, and the files the
+/// run created are listed as they would be removed.
+pub fn undo(
+	gctx: &GlobalContext,
+	ws: &Workspace<'_>,
+	editor: &ManifestEditor,
+	list: bool,
+	id: Option<String>,
+	force: bool,
+) -> Result<(), anyhow::Error> {
+	let journals = journals(ws)?;
+	if list {
+		for journal in journals.iter() {
+			println!("{}: {} ({} files)", journal.id, journal.command, journal.files.len());
+		}
+		return Ok(());
+	}
+
+	let journal = match id {
+		Some(id) => journals
+			.iter()
+			.find(|j| j.id == id)
+			.ok_or_else(|| anyhow::anyhow!("No journal `{}`, see `undo --list`", id))?,
+		None => journals.first().ok_or_else(|| anyhow::anyhow!("Nothing to undo"))?,
+	};
+
+	let modified = journal.modified_since();
+	if !modified.is_empty() && !force {
+		anyhow::bail!(
+			"Aborting: {} changed since `{}`, use `--force` to restore anyways.",
+			Vec::from_iter(modified.iter().map(|p| p.display().to_string())).join(", "),
+			journal.command
+		)
+	}
+
+	if editor.is_dry_run() {
+		for file in journal.files.iter() {
+			match &file.original {
+				Some(original) => editor.write(&file.path, original.clone())?,
+				None => gctx.shell().status(
+					"Removing",
+					format!("{} (dry run, nothing removed)", file.path.display()),
+				)?,
+			}
+		}
+		for m in journal.moves.iter().rev() {
+			editor.move_dir(&m.to, &m.from)?;
+		}
+		return Ok(())
+	}
+
+	journal.restore(ws)?;
+	for m in journal.moves.iter().rev() {
+		gctx.shell()
+			.status("Moved", format!("{} back to {}", m.to.display(), m.from.display()))?;
+	}
+	gctx.shell().status(
+		"Restored",
+		format!("{} manifests changed by `{}`", journal.files.len(), journal.command),
+	)?;
+	Ok(())
+}
@@ -1,35 +1,14 @@
-use crate::util::{edit_each, members_deep};
+use crate::{
+	editor::ManifestEditor,
+	util::{dependency_tables, edit_each, members_deep, workspace_first, SortableTableKeysBy},
+};
 
 use anyhow::{bail, Context};
 use cargo::core::package::Package;
 
 use cargo::{core::Workspace, GlobalContext};
 use cargo_util_schemas::manifest::TomlManifest;
-use toml_edit::{Formatted, InlineTable, Item, Key, Table, TableLike, Value};
-
-trait SortableTableKeysBy {
-	fn sort_values_by<F>(&mut self, compare: F)
-	where
-		F: FnMut(&Key, &Key) -> std::cmp::Ordering;
-}
-
-impl SortableTableKeysBy for Table {
-	fn sort_values_by<F>(&mut self, mut compare: F)
-	where
-		F: FnMut(&Key, &Key) -> std::cmp::Ordering,
-	{
-		Table::sort_values_by(self, move |k1, _, k2, _| compare(k1, k2));
-	}
-}
-
-impl SortableTableKeysBy for InlineTable {
-	fn sort_values_by<F>(&mut self, mut compare: F)
-	where
-		F: FnMut(&Key, &Key) -> std::cmp::Ordering,
-	{
-		InlineTable::sort_values_by(self, move |k1, _, k2, _| compare(k1, k2));
-	}
-}
+use toml_edit::{Formatted, InlineTable, Item, Table, TableLike, Value};
 
 fn log(gctx: &GlobalContext, packet: &str, dep: &str, ver: &str) {
 	gctx.shell()
@@ -52,14 +31,7 @@ fn replace_version_by_workspace<T: TableLike + SortableTableKeysBy + std::fmt::D
 		"workspace",
 		Item::Value(Value::Boolean(Formatted::new(true)).decorated(" ", suffix)),
 	);
-	// ensure `workspace = true` is the first key
-	tablelike.sort_values_by(|key1, _key2| {
-		if key1.get() == "workspace" {
-			std::cmp::Ordering::Less
-		} else {
-			std::cmp::Ordering::Equal
-		}
-	});
+	workspace_first(tablelike);
 
 	log(gctx, packet, dep_name, version.as_str().unwrap_or_default());
 }
@@ -68,6 +40,7 @@ fn replace_version_by_workspace<T: TableLike + SortableTableKeysBy + std::fmt::D
 pub fn unify_dependencies<P>(
 	gctx: &GlobalContext,
 	ws: &mut Workspace<'_>,
+	editor: &ManifestEditor,
 	predicate: P,
 ) -> Result<(), anyhow::Error>
 where
@@ -76,16 +49,14 @@ where
 	let manifest_path = ws.root_manifest().to_path_buf();
 	let root_ws_content = std::fs::read_to_string(&manifest_path)
 		.context(format!("Failed to read root manifest {}", manifest_path.display()))?;
-	let dependencies_to_unify: TomlManifest = toml::from_str(dbg!(&root_ws_content))?;
+	let dependencies_to_unify: TomlManifest = toml::from_str(&root_ws_content)?;
 	let Some(dependencies_to_unify) = dependencies_to_unify.workspace.unwrap().dependencies else {
 		bail!("No workspace level dependencies, nothing to unify")
 	};
 
-	edit_each(members_deep(gctx, ws).iter().filter(|p| predicate(p)), |p, doc| {
-		let per_table = |deps: &mut Item| {
-			let Some(deps) = deps.as_table_mut() else { return Ok(()) };
-
-			for (dep_name, _dep) in &dependencies_to_unify {
+	edit_each(editor, members_deep(gctx, ws).iter().filter(|p| predicate(p)), |p, doc| {
+		let per_table = |deps: &mut Table| {
+			for dep_name in dependencies_to_unify.keys() {
 				match deps.entry(dep_name.as_str()) {
 					toml_edit::Entry::Vacant(_) => {},
 					toml_edit::Entry::Occupied(mut occ) => {
@@ -130,8 +101,9 @@ where
 			}
 			Ok(())
 		};
-		per_table(&mut doc["dependencies"])?;
-		per_table(&mut doc["dev-dependencies"])?;
+		for (_, deps) in dependency_tables(doc.as_table_mut()) {
+			per_table(deps)?;
+		}
 		Ok(())
 	})?;
 
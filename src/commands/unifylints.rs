@@ -0,0 +1,267 @@
+use crate::{
+	editor::ManifestEditor,
+	util::{edit_each, edit_root, normalized},
+};
+use cargo::{
+	core::{package::Package, Workspace},
+	GlobalContext,
+};
+use std::collections::BTreeMap;
+use toml_edit::{DocumentMut, InlineTable, Item, Table, TableLike, Value};
+
+/// How the lints of the members are combined into `[workspace.lints]`
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LintPolicy {
+	/// Every lint any member sets
+	Union,
+	/// Only the lints all members set the same
+	Intersection,
+	/// The lints more than half of the members set the same
+	#[default]
+	MostCommon,
+}
+
+/// The level of each lint, by tool and name
+type Lints = BTreeMap<(String, String), Value>;
+
+/// The lints of a `[lints]` or `[workspace.lints]` table
+fn lints_of(table: &dyn TableLike) -> Lints {
+	let mut lints = BTreeMap::new();
+	for (tool, tool_lints) in table.iter() {
+		let Some(tool_lints) = tool_lints.as_table_like() else { continue };
+		for (name, level) in tool_lints.iter() {
+			if let Ok(level) = level.clone().into_value() {
+				lints.insert((tool.to_owned(), name.to_owned()), level);
+			}
+		}
+	}
+	lints
+}
+
+fn lint_name((tool, name): &(String, String)) -> String {
+	format!("{}::{}", tool, name)
+}
+
+/// Combine the lints of the members according to `policy`, using the level most of them set
+fn unified(members: &[Lints], policy: LintPolicy) -> Lints {
+	// lint -> normalized level -> (level, members setting it)
+	let mut levels = BTreeMap::<&(String, String), BTreeMap<String, (&Value, usize)>>::new();
+	for lints in members {
+		for (lint, level) in lints {
+			levels.entry(lint).or_default().entry(normalized(level)).or_insert((level, 0)).1 += 1;
+		}
+	}
+
+	let mut unified = BTreeMap::new();
+	for (lint, levels) in levels {
+		let (level, count) = levels
+			.values()
+			.max_by_key(|(_, count)| *count)
+			.expect("Only lints set by a member are collected. qed");
+		let keep = match policy {
+			LintPolicy::Union => true,
+			LintPolicy::Intersection => *count == members.len(),
+			LintPolicy::MostCommon => count * 2 > members.len(),
+		};
+		if keep {
+			unified.insert(lint.clone(), (*level).clone());
+		}
+	}
+	unified
+}
+
+/// The lints of `lints` not set the same in `workspace`
+fn overrides<'l>(lints: &'l Lints, workspace: &Lints) -> Vec<&'l (String, String)> {
+	Vec::from_iter(
+		lints
+			.iter()
+			.filter(|(lint, level)| workspace.get(*lint).map(normalized) != Some(normalized(level)))
+			.map(|(lint, _)| lint),
+	)
+}
+
+/// The local lints of the package, `None` if it inherits them or sets none
+fn member_lints(editor: &ManifestEditor, pkg: &Package) -> Result<Option<Lints>, anyhow::Error> {
+	let doc: DocumentMut = editor.read(pkg.manifest_path())?.parse()?;
+	let Some(lints) = doc.get("lints").and_then(Item::as_table_like) else { return Ok(None) };
+	if lints.get("workspace").and_then(Item::as_bool).unwrap_or_default() {
+		return Ok(None);
+	}
+	let lints = lints_of(lints);
+	Ok((!lints.is_empty()).then_some(lints))
+}
+
+/// Compute `[workspace.lints]` from the `[lints]` of the selected members according to `policy`
+/// and let the members inherit them, keeping lints already in `[workspace.lints]`. Members
+/// setting lints differently keep their own, as cargo can't combine both.
+///
+/// With `check_only`, fail if a member sets lints differently than `[workspace.lints]` instead,
+/// unless they are listed in `allowed` as `<tool>::<lint>`.
+pub fn unify_lints<P>(
+	gctx: &GlobalContext,
+	ws: &Workspace<'_>,
+	editor: &ManifestEditor,
+	predicate: P,
+	policy: LintPolicy,
+	allowed: &[String],
+	check_only: bool,
+) -> Result<(), anyhow::Error>
+where
+	P: Fn(&Package) -> bool,
+{
+	let root: DocumentMut = editor.read(ws.root_manifest())?.parse()?;
+	let existing = root
+		.get("workspace")
+		.and_then(|w| w.get("lints"))
+		.and_then(Item::as_table_like)
+		.map(lints_of)
+		.unwrap_or_default();
+
+	let mut members = Vec::new();
+	for pkg in ws.members().filter(|p| predicate(p)) {
+		if let Some(lints) = member_lints(editor, pkg)? {
+			members.push((pkg, lints));
+		}
+	}
+
+	if check_only {
+		let mut failures = Vec::new();
+		for (pkg, lints) in members.iter() {
+			let not_allowed = Vec::from_iter(
+				overrides(lints, &existing)
+					.into_iter()
+					.map(lint_name)
+					.filter(|lint| !allowed.contains(lint)),
+			);
+			if not_allowed.is_empty() {
+				gctx.shell().warn(format!(
+					"{} could inherit the workspace lints with `lints.workspace = true`",
+					pkg.name()
+				))?;
+			} else {
+				failures.push(format!("{} overrides {}", pkg.name(), not_allowed.join(", ")));
+			}
+		}
+		if !failures.is_empty() {
+			anyhow::bail!("Aborting: lints not allowed to be overridden: {}", failures.join("; "));
+		}
+		return Ok(());
+	}
+
+	if members.is_empty() {
+		gctx.shell().status("Done", "No member sets its own lints")?;
+		return Ok(());
+	}
+
+	let mut workspace = unified(&Vec::from_iter(members.iter().map(|(_, l)| l.clone())), policy);
+	workspace.extend(existing.clone());
+	let added = Vec::from_iter(workspace.iter().filter(|(lint, _)| !existing.contains_key(*lint)));
+	if !added.is_empty() {
+		gctx.shell()
+			.status("Unifying", format!("{} lints into `[workspace.lints]`", added.len()))?;
+		edit_root(editor, ws, |doc| {
+			let workspace = doc
+				.as_table_mut()
+				.entry("workspace")
+				.or_insert_with(|| Item::Table(Table::new()))
+				.as_table_mut()
+				.ok_or_else(|| anyhow::anyhow!("`workspace` isn't a table"))?;
+			let lints = workspace
+				.entry("lints")
+				.or_insert_with(|| {
+					let mut table = Table::new();
+					table.set_implicit(true);
+					Item::Table(table)
+				})
+				.as_table_like_mut()
+				.ok_or_else(|| anyhow::anyhow!("`workspace.lints` isn't a table"))?;
+			for ((tool, name), level) in added.iter() {
+				let tool_lints = lints
+					.entry(tool)
+					.or_insert_with(|| Item::Table(Table::new()))
+					.as_table_like_mut()
+					.ok_or_else(|| anyhow::anyhow!("`workspace.lints.{}` isn't a table", tool))?;
+				tool_lints.insert(name, Item::Value((*level).clone().decorated(" ", "")));
+			}
+			Ok(())
+		})?;
+	}
+
+	let inheriting = Vec::from_iter(members.iter().filter_map(|(pkg, lints)| {
+		let overridden = overrides(lints, &workspace);
+		if overridden.is_empty() {
+			return Some(pkg.name());
+		}
+		let _ = gctx.shell().warn(format!(
+			"{} keeps its own lints, it sets {} differently",
+			pkg.name(),
+			Vec::from_iter(overridden.into_iter().map(lint_name)).join(", ")
+		));
+		None
+	}));
+	edit_each(editor, members.iter().map(|(pkg, _)| *pkg), |p, doc| {
+		if !inheriting.contains(&p.name()) {
+			return Ok(());
+		}
+		gctx.shell().status("Inheriting", format!("workspace lints in {}", p.name()))?;
+		match doc.get_mut("lints") {
+			Some(Item::Table(lints)) => {
+				lints.clear();
+				lints.insert("workspace", Item::Value(Value::from(true)));
+			},
+			Some(lints) => {
+				let mut inline = InlineTable::new();
+				inline.insert("workspace", Value::from(true));
+				*lints = Item::Value(Value::InlineTable(inline));
+			},
+			None => {},
+		}
+		Ok(())
+	})?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn lints(levels: &[(&str, &str)]) -> Lints {
+		Lints::from_iter(levels.iter().map(|(name, level)| {
+			(("clippy".to_owned(), name.to_string()), Value::from(level.to_string()))
+		}))
+	}
+
+	#[test]
+	fn policies() {
+		let members = [
+			lints(&[("all", "warn"), ("unwrap_used", "deny")]),
+			lints(&[("all", "warn"), ("unwrap_used", "warn")]),
+			lints(&[("all", "warn"), ("unwrap_used", "deny"), ("todo", "deny")]),
+		];
+		let names =
+			|lints: Lints| {
+				Vec::from_iter(lints.iter().map(|(lint, level)| {
+					format!("{}={}", lint_name(lint), level.as_str().unwrap())
+				}))
+			};
+		assert_eq!(
+			names(unified(&members, LintPolicy::Union)),
+			vec!["clippy::all=warn", "clippy::todo=deny", "clippy::unwrap_used=deny"]
+		);
+		assert_eq!(names(unified(&members, LintPolicy::Intersection)), vec!["clippy::all=warn"]);
+		assert_eq!(
+			names(unified(&members, LintPolicy::MostCommon)),
+			vec!["clippy::all=warn", "clippy::unwrap_used=deny"]
+		);
+	}
+
+	#[test]
+	fn overridden() {
+		let workspace = lints(&[("all", "warn"), ("unwrap_used", "deny")]);
+		let member = lints(&[("all", "warn"), ("unwrap_used", "warn"), ("todo", "deny")]);
+		assert_eq!(
+			Vec::from_iter(overrides(&member, &workspace).into_iter().map(lint_name)),
+			vec!["clippy::todo", "clippy::unwrap_used"]
+		);
+	}
+}
@@ -1,19 +1,43 @@
 use crate::{
 	cli::VersionCommand,
+	commands::lint_versions,
+	config::{workspace_config, RequirementStyle},
+	editor::ManifestEditor,
+	registry::published_versions,
+	template::expand_git,
 	util::{
-		edit_each, edit_each_dep, make_pkg_predicate, members_deep, DependencyAction,
-		DependencyEntry, DependencySection,
+		edit_each, edit_each_dep, edit_root, edit_workspace_deps, make_pkg_predicate, members_deep,
+		DependencyAction, DependencyEntry, DependencySection,
 	},
 };
 use anyhow::Context;
 use cargo::{
-	core::{package::Package, Workspace},
+	core::{dependency::DepKind, package::Package, Dependency, Workspace},
+	util::interning::InternedString,
 	GlobalContext,
 };
 use log::trace;
-use semver::{BuildMetadata, Prerelease, Version, VersionReq};
-use std::collections::HashMap;
-use toml_edit::{Entry, Item, Value};
+use semver::{BuildMetadata, Comparator, Op, Prerelease, Version, VersionReq};
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap},
+	fs,
+	io::IsTerminal,
+	path::{Path, PathBuf},
+};
+use toml_edit::{DocumentMut, Item, Value};
+
+/// The requirement to write for `version`: in the configured `style`, or else in the style of
+/// the `existing` requirement.
+fn requirement(
+	style: Option<RequirementStyle>,
+	existing: Option<&str>,
+	version: &Version,
+) -> String {
+	style
+		.or_else(|| existing.map(RequirementStyle::of))
+		.unwrap_or_default()
+		.requirement(version)
+}
 
 fn check_for_update(
 	name: String,
@@ -21,6 +45,7 @@ fn check_for_update(
 	updates: &HashMap<String, Version>,
 	section: DependencySection,
 	force_update: bool,
+	style: Option<RequirementStyle>,
 ) -> DependencyAction {
 	let new_version = if let Some(v) = updates.get(&name) {
 		v
@@ -44,20 +69,18 @@ fn check_for_update(
 					.expect("Cargo enforces us using semver versions. qed");
 				if force_update || !r.matches(new_version) {
 					trace!("Versions don't match anymore, updating.");
-					*v_req = Value::from(format!("{:}", new_version)).decorated(" ", "");
+					*v_req = Value::from(requirement(style, v_req.as_str(), new_version))
+						.decorated(" ", "");
 					return DependencyAction::Mutated;
 				}
-			} else if section == DependencySection::Dev {
+			} else if section.is_dev() {
 				trace!("No version found on dev dependency, ignoring.");
 				return DependencyAction::Untouched;
 			} else {
 				// not yet present, we force set.
 				trace!("No version found, setting.");
-				// having a space here means we formatting it nicer inline
-				info.get_or_insert(
-					" version",
-					Value::from(format!("{:}", new_version)).decorated(" ", " "),
-				);
+				info.insert("version", Value::from(requirement(style, None, new_version)));
+				info.fmt();
 				return DependencyAction::Mutated;
 			}
 		},
@@ -78,14 +101,15 @@ fn check_for_update(
 						return DependencyAction::Untouched;
 					}
 					trace!("Versions don't match anymore, updating.");
-				} else if section == DependencySection::Dev {
+				} else if section.is_dev() {
 					trace!("No version found on dev dependency {:}, ignoring.", name);
 					return DependencyAction::Untouched;
 				} else {
 					trace!("No version found, setting.");
 				}
-				info["version"] =
-					Item::Value(Value::from(format!("{:}", new_version)).decorated(" ", ""));
+				let req =
+					requirement(style, info.get("version").and_then(|v| v.as_str()), new_version);
+				info["version"] = Item::Value(Value::from(req).decorated(" ", ""));
 				return DependencyAction::Mutated;
 			}
 		},
@@ -93,76 +117,453 @@ fn check_for_update(
 	DependencyAction::Untouched
 }
 
+/// The new versions of the packages about to be bumped, in workspace order
+type VersionPlan = Vec<(Package, Version)>;
+
+/// Ensure all members of a version group, of which any member is part of the `plan`, end up
+/// with the same version: the maximum of their computed versions.
+fn apply_version_groups<M>(
+	members: &[Package],
+	groups: &BTreeMap<String, Vec<String>>,
+	plan: &mut VersionPlan,
+	mapper: M,
+) -> Result<(), anyhow::Error>
+where
+	M: Fn(&Package) -> Option<Version>,
+{
+	for (group, names) in groups {
+		if !plan.iter().any(|(p, _)| names.iter().any(|n| p.name() == n.as_str())) {
+			continue;
+		}
+
+		let group_members = names
+			.iter()
+			.map(|name| {
+				members.iter().find(|p| p.name() == name.as_str()).ok_or_else(|| {
+					anyhow::anyhow!("Unknown package `{}` in version group `{}`", name, group)
+				})
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let version = group_members
+			.iter()
+			.map(|p| match plan.iter().find(|(planned, _)| planned.name() == p.name()) {
+				Some((_, v)) => v.clone(),
+				None => mapper(p).unwrap_or_else(|| p.version().clone()),
+			})
+			.max()
+			.expect("The group has at least the member in the plan. qed");
+
+		trace!("Version group {} moves to {}", group, version);
+		for p in group_members {
+			match plan.iter_mut().find(|(planned, _)| planned.name() == p.name()) {
+				Some((_, v)) => *v = version.clone(),
+				None => plan.push((p.clone(), version.clone())),
+			}
+		}
+	}
+	Ok(())
+}
+
+/// Whether moving from `old` to `new` is incompatible, i.e. `^old` doesn't match `new`
+fn is_breaking(old: &Version, new: &Version) -> bool {
+	let req = Comparator {
+		op: Op::Caret,
+		major: old.major,
+		minor: Some(old.minor),
+		patch: Some(old.patch),
+		pre: old.pre.clone(),
+	};
+	!req.matches(new)
+}
+
+fn rust_files(dir: &Path) -> Vec<PathBuf> {
+	let mut files = Vec::new();
+	if let Ok(entries) = fs::read_dir(dir) {
+		for entry in entries.flatten() {
+			let path = entry.path();
+			if path.is_dir() {
+				files.extend(rust_files(&path));
+			} else if path.extension().map(|e| e == "rs").unwrap_or_default() {
+				files.push(path);
+			}
+		}
+	}
+	files
+}
+
+/// Whether `source` contains a `pub use` or `pub extern crate` of the crate `ident`
+fn has_pub_use(source: &str, ident: &str) -> bool {
+	source.lines().map(str::trim_start).any(|line| {
+		["pub use ", "pub use ::", "pub extern crate "].iter().any(|prefix| {
+			line.strip_prefix(prefix)
+				.and_then(|rest| rest.strip_prefix(ident))
+				.map(|rest| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'))
+				.unwrap_or_default()
+		})
+	})
+}
+
+/// Whether `pkg` exposes `dep` in its public API: the dependency is declared `public = true`
+/// or any of its sources re-exports it.
+fn reexports(pkg: &Package, dep: &Dependency) -> bool {
+	if dep.is_public() {
+		return true;
+	}
+	let ident = dep.name_in_toml().replace('-', "_");
+	rust_files(&pkg.root().join("src"))
+		.iter()
+		.any(|file| fs::read_to_string(file).map(|c| has_pub_use(&c, &ident)).unwrap_or_default())
+}
+
+/// Extend the `plan` by the dependents of every package bumped incompatibly: dependents
+/// re-exporting it get a breaking bump, all others a patch bump. Both are cascaded further if
+/// incompatible, as patch bumps of `0.0.x` versions are.
+/// Dependents on a pre-release aren't published in that version yet and are left as they are.
+///
+/// Returns why each package was added or changed.
+fn cascade_plan(members: &[Package], plan: &mut VersionPlan) -> BTreeMap<String, String> {
+	let mut reasons = BTreeMap::new();
+	let mut queue = Vec::from_iter(
+		plan.iter().filter(|(p, v)| is_breaking(p.version(), v)).map(|(p, _)| p.name()),
+	);
+	while let Some(bumped) = queue.pop() {
+		for dependent in members.iter().filter(|m| m.version().pre.is_empty()) {
+			let dep = match dependent.dependencies().iter().find(|d| {
+				d.package_name() == bumped &&
+					d.source_id().is_path() &&
+					d.kind() != DepKind::Development
+			}) {
+				Some(dep) => dep,
+				None => continue,
+			};
+			let breaking = reexports(dependent, dep);
+			let planned = plan.iter_mut().find(|(p, _)| p.name() == dependent.name());
+			if let Some((_, v)) = &planned {
+				if !breaking || is_breaking(dependent.version(), v) {
+					continue;
+				}
+			}
+
+			let mut version = dependent.version().clone();
+			if breaking {
+				bump_breaking_version(&mut version);
+				reasons.insert(dependent.name().to_string(), format!("re-exports {}", bumped));
+			} else {
+				bump_patch_version(&mut version);
+				reasons.insert(dependent.name().to_string(), format!("depends on {}", bumped));
+			}
+			// a patch bump of `0.0.x` is incompatible as well
+			if is_breaking(dependent.version(), &version) {
+				queue.push(dependent.name());
+			}
+			match planned {
+				Some((_, v)) => *v = version,
+				None => plan.push((dependent.clone(), version)),
+			}
+		}
+	}
+	reasons
+}
+
+/// Fail if members of a version group have drifted apart
+pub fn check_version_groups(gctx: &GlobalContext, ws: &Workspace<'_>) -> Result<(), anyhow::Error> {
+	let members = members_deep(gctx, ws);
+	let mut errors = Vec::new();
+	for (group, names) in workspace_config(ws)?.version_groups {
+		let versions = BTreeSet::from_iter(
+			members
+				.iter()
+				.filter(|p| names.iter().any(|n| p.name() == n.as_str()))
+				.map(|p| p.version().clone()),
+		);
+		if versions.len() > 1 {
+			errors.push(format!(
+				"{}: {}",
+				group,
+				Vec::from_iter(versions.iter().map(|v| v.to_string())).join(", ")
+			));
+		}
+	}
+	if !errors.is_empty() {
+		anyhow::bail!("Version groups have drifted apart: {}", errors.join("; "));
+	}
+	Ok(())
+}
+
+/// Whether version bumps are cascaded to dependents, see [`cascade_plan`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cascade {
+	/// Only the selected packages are bumped
+	No,
+	/// The cascaded plan is applied once confirmed
+	Confirm,
+	/// The cascaded plan is applied right away
+	Yes,
+}
+
+impl Cascade {
+	fn new(cascade: bool, yes: bool) -> Cascade {
+		match (cascade, yes) {
+			(false, _) => Cascade::No,
+			(true, false) => Cascade::Confirm,
+			(true, true) => Cascade::Yes,
+		}
+	}
+}
+
+/// Ask on the terminal whether to apply the planned versions, fails if there is none to ask
+fn confirm_plan(gctx: &GlobalContext) -> Result<(), anyhow::Error> {
+	if !std::io::stdin().is_terminal() {
+		anyhow::bail!("Aborting: can't confirm the cascaded versions, pass `--yes` to apply them");
+	}
+	gctx.shell().print_ansi_stderr(b"Apply the planned versions? [y/N] ")?;
+	let mut answer = String::new();
+	std::io::stdin().read_line(&mut answer)?;
+	if !matches!(answer.trim(), "y" | "Y" | "yes") {
+		anyhow::bail!("Aborting: the planned versions weren't confirmed");
+	}
+	Ok(())
+}
+
+/// Whether the package uses `version.workspace = true`
+fn inherits_version(pkg: &Package) -> Result<bool, anyhow::Error> {
+	let doc: DocumentMut = fs::read_to_string(pkg.manifest_path())?.parse()?;
+	Ok(doc
+		.get("package")
+		.and_then(|p| p.get("version"))
+		.and_then(|v| v.get("workspace"))
+		.and_then(|w| w.as_bool())
+		.unwrap_or_default())
+}
+
 /// For packages matching predicate set to mapper given version, if any. Update all members
 /// dependencies if necessary.
+///
+/// Members of a version group declared in `[workspace.metadata.dragons.version-groups]` are
+/// always set to the same version. Packages inheriting `version.workspace = true` are bumped by
+/// updating `[workspace.package]` in the root manifest once, they can't be mixed with packages
+/// having their own version.
+///
+/// With `cascade`, dependents of incompatibly bumped packages are bumped as well, see
+/// [`cascade_plan`]. The resulting plan is shown before anything is written and, unless
+/// [`Cascade::Yes`] or on dry runs, applied only if confirmed.
 pub fn set_version<M, P>(
 	gctx: &GlobalContext,
 	ws: &Workspace<'_>,
+	editor: &ManifestEditor,
 	predicate: P,
 	mapper: M,
 	force_update: bool,
+	cascade: Cascade,
 ) -> Result<(), anyhow::Error>
 where
 	P: Fn(&Package) -> bool,
 	M: Fn(&Package) -> Option<Version>,
 {
-	let updates = HashMap::<String, Version>::from_iter(
-		edit_each(members_deep(gctx, ws).iter().filter(|p| predicate(p)), |p, doc| {
-			Ok(mapper(p).map(|nv_version| {
-				gctx.shell()
-					.status(
-						"Bumping",
-						format!("{:}: {:} -> {:}", p.name(), p.version(), nv_version),
-					)
-					.expect("Writing to the shell would have failed before. qed");
-				doc["package"]["version"] =
-					Item::Value(Value::from(nv_version.to_string()).decorated(" ", ""));
-				(p.name().as_str().to_owned(), nv_version)
-			}))
-		})?
-		.into_iter()
-		.flatten(),
-	);
+	let members = members_deep(gctx, ws);
+	let mut plan = VersionPlan::new();
+	for p in members.iter().filter(|p| predicate(p)) {
+		if plan.iter().any(|(planned, _)| planned.name() == p.name()) {
+			continue;
+		}
+		if let Some(version) = mapper(p) {
+			plan.push((p.clone(), version));
+		}
+	}
+	let ws_config = workspace_config(ws)?;
+	let groups = &ws_config.version_groups;
+	apply_version_groups(&members, groups, &mut plan, &mapper)?;
+
+	if cascade != Cascade::No {
+		let reasons = cascade_plan(&members, &mut plan);
+		// groups touched by the cascade only follow the cascaded versions
+		apply_version_groups(&members, groups, &mut plan, |_| None)?;
+		for (p, version) in plan.iter() {
+			let reason =
+				reasons.get(p.name().as_str()).map(|r| format!(" ({})", r)).unwrap_or_default();
+			gctx.shell().status(
+				"Planned",
+				format!("{}: {} -> {}{}", p.name(), p.version(), version, reason),
+			)?;
+		}
+		if cascade == Cascade::Confirm && !reasons.is_empty() && !editor.is_dry_run() {
+			confirm_plan(gctx)?;
+		}
+	}
+
+	let mut inheriting = VersionPlan::new();
+	let mut explicit = VersionPlan::new();
+	for (p, version) in plan {
+		if inherits_version(&p)? {
+			inheriting.push((p, version));
+		} else {
+			explicit.push((p, version));
+		}
+	}
+	if !inheriting.is_empty() && !explicit.is_empty() {
+		let names = |plan: &VersionPlan| {
+			Vec::from_iter(plan.iter().map(|(p, _)| p.name().to_string())).join(", ")
+		};
+		anyhow::bail!(
+			"Can't mix packages inheriting the workspace version ({}) with packages having an explicit version ({}) in one selection",
+			names(&inheriting),
+			names(&explicit)
+		);
+	}
+
+	let mut updates = HashMap::<String, Version>::from_iter(edit_each(
+		editor,
+		explicit.iter().map(|(p, _)| p),
+		|p, doc| {
+			let nv_version = explicit
+				.iter()
+				.find_map(|(planned, v)| (planned.name() == p.name()).then_some(v))
+				.expect("Iterating the plan. qed");
+			gctx.shell()
+				.status("Bumping", format!("{:}: {:} -> {:}", p.name(), p.version(), nv_version))
+				.expect("Writing to the shell would have failed before. qed");
+			doc["package"]["version"] =
+				Item::Value(Value::from(nv_version.to_string()).decorated(" ", ""));
+			Ok((p.name().as_str().to_owned(), nv_version.clone()))
+		},
+	)?);
+
+	if let Some((p, nv_version)) = inheriting.first() {
+		if inheriting.iter().any(|(_, v)| v != nv_version) {
+			anyhow::bail!(
+				"Packages inheriting the workspace version would end up with different versions: {}",
+				Vec::from_iter(inheriting.iter().map(|(p, v)| format!("{} ({})", p.name(), v)))
+					.join(", ")
+			);
+		}
+		gctx.shell().status(
+			"Bumping",
+			format!("[workspace.package]: {:} -> {:}", p.version(), nv_version),
+		)?;
+		edit_root(editor, ws, |doc| {
+			let version = doc
+				.get_mut("workspace")
+				.and_then(|w| w.get_mut("package"))
+				.and_then(|p| p.get_mut("version"))
+				.ok_or_else(|| anyhow::anyhow!("No `[workspace.package]` version to inherit"))?;
+			*version = Item::Value(Value::from(nv_version.to_string()).decorated(" ", ""));
+			Ok(())
+		})?;
+
+		// every package inheriting the version moves along, whether selected or not
+		for m in members.iter() {
+			if !updates.contains_key(m.name().as_str()) && inherits_version(m)? {
+				gctx.shell().status(
+					"Inheriting",
+					format!("{:}: {:} -> {:}", m.name(), m.version(), nv_version),
+				)?;
+				updates.insert(m.name().as_str().to_owned(), nv_version.clone());
+			}
+		}
+	}
 
 	gctx.shell().status("Updating", "Dependency tree")?;
-	edit_each(members_deep(gctx, ws).iter(), |p, doc| {
+	edit_each(editor, members_deep(gctx, ws).iter(), |p, doc| {
 		gctx.shell().status("Updating", p.name())?;
 		let root = doc.as_table_mut();
-		let mut updates_count = 0;
-		updates_count += edit_each_dep(root, |name, _, wrap, section| {
-			check_for_update(name, wrap, &updates, section, force_update)
+		let updates_count = edit_each_dep(root, |name, _, wrap, section| {
+			check_for_update(
+				name,
+				wrap,
+				&updates,
+				section,
+				force_update,
+				ws_config.requirement_style,
+			)
 		});
 
-		if let Entry::Occupied(occupied) = root.entry("target") {
-			if let Item::Table(table) = occupied.get() {
-				let keys = Vec::from_iter(table.iter().filter_map(|(k, v)| {
-					if v.is_table() {
-						Some(k.to_owned())
-					} else {
-						None
-					}
-				}));
-
-				for k in keys {
-					if let Some(Item::Table(root)) = root.get_mut(&k) {
-						updates_count += edit_each_dep(root, |a, _, b, c| {
-							check_for_update(a, b, &updates, c, force_update)
-						});
-					}
-				}
-			}
-		}
 		let status = "Done";
-		let mut status_message = format!("{} dependencies updated", updates_count);
-		if updates_count == 0 {
-			status_message = "No dependency updates".to_owned();
-		} else if updates_count == 1 {
-			status_message = "One dependency updated".to_owned();
-		}
+		let status_message = match updates_count {
+			0 => "No dependency updates".to_owned(),
+			1 => "One dependency updated".to_owned(),
+			n => format!("{} dependencies updated", n),
+		};
 		gctx.shell().status(status, status_message)?;
 		Ok(())
 	})?;
 
+	let updates_count = edit_root(editor, ws, |doc| {
+		Ok(edit_workspace_deps(doc.as_table_mut(), |name, _, wrap, section| {
+			check_for_update(
+				name,
+				wrap,
+				&updates,
+				section,
+				force_update,
+				ws_config.requirement_style,
+			)
+		}))
+	})?;
+	if updates_count > 0 {
+		gctx.shell()
+			.status("Updated", format!("{} entries of [workspace.dependencies]", updates_count))?;
+	}
+
+	Ok(())
+}
+
+/// Rewrite every requirement on a member in the given style, or the configured one
+fn normalize_requirements(
+	gctx: &GlobalContext,
+	ws: &Workspace<'_>,
+	editor: &ManifestEditor,
+	style: Option<RequirementStyle>,
+) -> Result<(), anyhow::Error> {
+	let style = match style.or(workspace_config(ws)?.requirement_style) {
+		Some(style) => style,
+		None => anyhow::bail!(
+			"No `--style` given and no `requirement-style` set in `[workspace.metadata.dragons]`"
+		),
+	};
+	let members = members_deep(gctx, ws);
+	let versions = HashMap::<String, Version>::from_iter(
+		members.iter().map(|p| (p.name().to_string(), p.version().clone())),
+	);
+	let normalize = |name: String, mut wrap: DependencyEntry<'_>, _| {
+		let version = match versions.get(&name) {
+			Some(version) if wrap.contains_key("path") => version,
+			_ => return DependencyAction::Untouched, // entry isn't local
+		};
+		let normalized = style.requirement(version);
+		// missing requirements are left to `version lint`
+		if wrap.get_str("version").map(|existing| existing == normalized).unwrap_or(true) {
+			return DependencyAction::Untouched;
+		}
+		wrap.set_requirement(normalized);
+		DependencyAction::Mutated
+	};
+
+	let counts = edit_each(editor, members.iter(), |p, doc| {
+		let count = edit_each_dep(doc.as_table_mut(), |name, _, wrap, section| {
+			normalize(name, wrap, section)
+		});
+		if count > 0 {
+			gctx.shell()
+				.status("Normalized", format!("{}: {} requirements", p.name(), count))?;
+		}
+		Ok(count)
+	})?;
+	let workspace_count = edit_root(editor, ws, |doc| {
+		Ok(edit_workspace_deps(doc.as_table_mut(), |name, _, wrap, section| {
+			normalize(name, wrap, section)
+		}))
+	})?;
+	if workspace_count > 0 {
+		gctx.shell().status(
+			"Normalized",
+			format!("[workspace.dependencies]: {} requirements", workspace_count),
+		)?;
+	}
+	gctx.shell().status(
+		"Done",
+		format!("{} requirements normalized", counts.iter().sum::<u32>() + workspace_count),
+	)?;
 	Ok(())
 }
 
@@ -183,54 +584,204 @@ fn bump_patch_version(v: &mut Version) {
 	v.patch += 1;
 }
 
+/// Bump major for major > 0, minor for 0.x and patch for 0.0.x, unset prerelease
+fn bump_breaking_version(v: &mut Version) {
+	v.pre = Prerelease::EMPTY;
+	if v.major != 0 {
+		bump_major_version(v);
+	} else if v.minor != 0 {
+		bump_minor_version(v);
+	} else {
+		bump_patch_version(v);
+		// no helper, have to reset the metadata ourselves
+		v.build = BuildMetadata::EMPTY;
+	}
+}
+
+/// Increase the trailing number of the pre-release, set to `.1` if there is none
+fn bump_pre_version(mut v: Version) -> Option<Version> {
+	if v.pre.is_empty() {
+		v.pre = Prerelease::new("1").expect("Static will work");
+	} else if let Ok(num) = v.pre.as_str().parse::<u32>() {
+		v.pre = Prerelease::new(&format!("{}", num + 1)).expect("Known to work");
+	} else {
+		let mut items = Vec::from_iter(v.pre.as_str().split('.').map(|s| s.to_string()));
+		if let Some(num) = items.last().and_then(|u| u.parse::<u32>().ok()) {
+			let _ = items.pop();
+			items.push(format!("{}", num + 1));
+		} else {
+			items.push("1".to_owned());
+		}
+		v.pre = Prerelease::new(&items.join(".")).ok()?;
+	}
+	Some(v)
+}
+
+/// Split a pre-release into its prefix and trailing number, e.g. `beta.2` into `beta` and `2`
+fn split_pre(pre: &str) -> (&str, Option<u64>) {
+	let (prefix, last) = pre.rsplit_once('.').unwrap_or(("", pre));
+	match last.parse() {
+		Ok(num) => (prefix, Some(num)),
+		Err(_) => (pre, None),
+	}
+}
+
+/// Raise the trailing number of the pre-release above the ones of all `published` pre-releases
+/// of the same version and prefix
+fn next_unused_pre(mut v: Version, published: Option<&[Version]>) -> Version {
+	let (prefix, num) = split_pre(v.pre.as_str());
+	let Some(num) = num else { return v };
+	let highest = published
+		.unwrap_or_default()
+		.iter()
+		.filter(|p| (p.major, p.minor, p.patch) == (v.major, v.minor, v.patch))
+		.filter_map(|p| match split_pre(p.pre.as_str()) {
+			(p_prefix, Some(p_num)) if p_prefix == prefix => Some(p_num),
+			_ => None,
+		})
+		.max();
+	match highest {
+		Some(highest) if highest >= num => {
+			let pre = if prefix.is_empty() {
+				format!("{}", highest + 1)
+			} else {
+				format!("{}.{}", prefix, highest + 1)
+			};
+			v.pre = Prerelease::new(&pre).expect("Only the number changed. qed");
+			v
+		},
+		_ => v,
+	}
+}
+
+/// Move the pre-release to the `target` channel, starting at `.1`, or to the release if `target`
+/// is `None`. Fails if it's on a later channel already, released versions and those on the
+/// `target` channel already aren't changed.
+fn promote_version(
+	v: &Version,
+	channels: &[String],
+	target: Option<&str>,
+) -> Result<Option<Version>, anyhow::Error> {
+	if v.pre.is_empty() {
+		return Ok(None);
+	}
+	let mut promoted = v.clone();
+	let Some(target) = target else {
+		promoted.pre = Prerelease::EMPTY;
+		return Ok(Some(promoted));
+	};
+
+	let channel = v.pre.as_str().split('.').next().expect("Split yields at least one item. qed");
+	let current = channels.iter().position(|c| c == channel);
+	let target_index = channels
+		.iter()
+		.position(|c| c == target)
+		.expect("The target is checked to be a channel before. qed");
+	match current {
+		Some(current) if current == target_index => return Ok(None),
+		Some(current) if current > target_index =>
+			anyhow::bail!("{} is on the later channel `{}` already", v, channel),
+		_ => {},
+	}
+	promoted.pre = Prerelease::new(&format!("{}.1", target))
+		.with_context(|| format!("`{}` isn't a valid pre-release", target))?;
+	Ok(Some(promoted))
+}
+
+/// The versions published of the selected packages, if `from_registry` is set
+fn registry_versions<P>(
+	gctx: &GlobalContext,
+	ws: &Workspace<'_>,
+	from_registry: bool,
+	predicate: P,
+) -> Result<HashMap<InternedString, Vec<Version>>, anyhow::Error>
+where
+	P: Fn(&Package) -> bool,
+{
+	if !from_registry {
+		return Ok(HashMap::new());
+	}
+	published_versions(
+		gctx,
+		members_deep(gctx, ws).iter().filter(|p| predicate(p)).map(|p| p.name()),
+	)
+}
+
 /// Adjust the version of the crate according to the given version adjustment command
 pub fn adjust_version(
 	gctx: &GlobalContext,
 	ws: &Workspace<'_>,
+	editor: &ManifestEditor,
 	cmd: VersionCommand,
 ) -> Result<(), anyhow::Error> {
 	match cmd {
 		VersionCommand::Set { pkg_opts, force_update, version } => {
 			let predicate = make_pkg_predicate(gctx, ws, pkg_opts)?;
-			set_version(gctx, ws, |p| predicate(p), |_| Some(version.clone()), force_update)
+			set_version(
+				gctx,
+				ws,
+				editor,
+				|p| predicate(p),
+				|_| Some(version.clone()),
+				force_update,
+				Cascade::No,
+			)
 		},
-		VersionCommand::BumpPre { pkg_opts, force_update } => {
+		VersionCommand::BumpPre { pkg_opts, force_update, cascade, yes, from_registry } => {
 			let predicate = make_pkg_predicate(gctx, ws, pkg_opts)?;
+			let published = registry_versions(gctx, ws, from_registry, &predicate)?;
 			set_version(
 				gctx,
 				ws,
+				editor,
 				|p| predicate(p),
 				|p| {
-					let mut v = p.version().clone();
-					if v.pre.is_empty() {
-						v.pre = Prerelease::new("1").expect("Static will work");
-					} else if let Ok(num) = v.pre.as_str().parse::<u32>() {
-						v.pre = Prerelease::new(&format!("{}", num + 1)).expect("Known to work");
-					} else {
-						let mut items =
-							Vec::from_iter(v.pre.as_str().split('.').map(|s| s.to_string()));
-						if let Some(num) = items.last().and_then(|u| u.parse::<u32>().ok()) {
-							let _ = items.pop();
-							items.push(format!("{}", num + 1));
-						} else {
-							items.push("1".to_owned());
-						}
-						if let Ok(pre) = Prerelease::new(&items.join(".")) {
-							v.pre = pre;
-						} else {
-							return None;
-						}
-					}
-					Some(v)
+					bump_pre_version(p.version().clone())
+						.map(|v| next_unused_pre(v, published.get(&p.name()).map(|v| &v[..])))
 				},
 				force_update,
+				Cascade::new(cascade, yes),
+			)
+		},
+		VersionCommand::Promote { pkg_opts, to, force_update, from_registry } => {
+			let channels = workspace_config(ws)?.pre_release_channels();
+			let target = match to.as_str() {
+				"release" => None,
+				channel if channels.iter().any(|c| c == channel) => Some(channel),
+				channel => anyhow::bail!(
+					"Unknown channel `{}`, expected `release` or one of: {}",
+					channel,
+					channels.join(", ")
+				),
+			};
+			let predicate = make_pkg_predicate(gctx, ws, pkg_opts)?;
+			let published = registry_versions(gctx, ws, from_registry, &predicate)?;
+
+			let mut promoted = HashMap::new();
+			for p in members_deep(gctx, ws).iter().filter(|p| predicate(p)) {
+				if let Some(v) = promote_version(p.version(), &channels, target)
+					.with_context(|| format!("Can't promote {}", p.name()))?
+				{
+					let v = next_unused_pre(v, published.get(&p.name()).map(|v| &v[..]));
+					promoted.insert(p.name(), v);
+				}
+			}
+			set_version(
+				gctx,
+				ws,
+				editor,
+				|p| predicate(p),
+				|p| promoted.get(&p.name()).cloned(),
+				force_update,
+				Cascade::No,
 			)
 		},
-		VersionCommand::BumpPatch { pkg_opts, force_update } => {
+		VersionCommand::BumpPatch { pkg_opts, force_update, cascade, yes } => {
 			let predicate = make_pkg_predicate(gctx, ws, pkg_opts)?;
 			set_version(
 				gctx,
 				ws,
+				editor,
 				|p| predicate(p),
 				|p| {
 					let mut v = p.version().clone();
@@ -239,13 +790,15 @@ pub fn adjust_version(
 					Some(v)
 				},
 				force_update,
+				Cascade::new(cascade, yes),
 			)
 		},
-		VersionCommand::BumpMinor { pkg_opts, force_update } => {
+		VersionCommand::BumpMinor { pkg_opts, force_update, cascade, yes } => {
 			let predicate = make_pkg_predicate(gctx, ws, pkg_opts)?;
 			set_version(
 				gctx,
 				ws,
+				editor,
 				|p| predicate(p),
 				|p| {
 					let mut v = p.version().clone();
@@ -254,13 +807,15 @@ pub fn adjust_version(
 					Some(v)
 				},
 				force_update,
+				Cascade::new(cascade, yes),
 			)
 		},
-		VersionCommand::BumpMajor { pkg_opts, force_update } => {
+		VersionCommand::BumpMajor { pkg_opts, force_update, cascade, yes } => {
 			let predicate = make_pkg_predicate(gctx, ws, pkg_opts)?;
 			set_version(
 				gctx,
 				ws,
+				editor,
 				|p| predicate(p),
 				|p| {
 					let mut v = p.version().clone();
@@ -269,83 +824,102 @@ pub fn adjust_version(
 					Some(v)
 				},
 				force_update,
+				Cascade::new(cascade, yes),
 			)
 		},
-		VersionCommand::BumpBreaking { pkg_opts, force_update } => {
+		VersionCommand::BumpBreaking { pkg_opts, force_update, cascade, yes } => {
 			let predicate = make_pkg_predicate(gctx, ws, pkg_opts)?;
 			set_version(
 				gctx,
 				ws,
+				editor,
 				|p| predicate(p),
 				|p| {
 					let mut v = p.version().clone();
-					v.pre = Prerelease::EMPTY;
-					if v.major != 0 {
-						bump_major_version(&mut v);
-					} else if v.minor != 0 {
-						bump_minor_version(&mut v);
-					} else {
-						bump_patch_version(&mut v);
-						// no helper, have to reset the metadata ourselves
-						v.build = BuildMetadata::EMPTY;
-					}
+					bump_breaking_version(&mut v);
 					Some(v)
 				},
 				force_update,
+				Cascade::new(cascade, yes),
 			)
 		},
-		VersionCommand::BumpToDev { pkg_opts, force_update, pre_tag } => {
+		VersionCommand::BumpToDev { pkg_opts, force_update, cascade, yes, pre_tag } => {
 			let predicate = make_pkg_predicate(gctx, ws, pkg_opts)?;
 			let pre_val = pre_tag.unwrap_or_else(|| "dev".to_owned());
 			set_version(
 				gctx,
 				ws,
+				editor,
 				|p| predicate(p),
 				|p| {
 					let mut v = p.version().clone();
-					if v.major != 0 {
-						bump_major_version(&mut v);
-					} else if v.minor != 0 {
-						bump_minor_version(&mut v);
-					} else {
-						bump_patch_version(&mut v);
-						// no helper, have to reset the metadata ourselves
-						v.build = BuildMetadata::EMPTY;
-					}
+					bump_breaking_version(&mut v);
 					// force the pre
 					v.pre = Prerelease::new(&pre_val.clone()).expect("Static or expected to work");
 					Some(v)
 				},
 				force_update,
+				Cascade::new(cascade, yes),
 			)
 		},
 		VersionCommand::SetPre { pre, pkg_opts, force_update } => {
+			let pre = expand_git(ws.root(), &pre)?;
+			let pre =
+				Prerelease::new(&pre).with_context(|| format!("Invalid pre-release `{}`", pre))?;
 			let predicate = make_pkg_predicate(gctx, ws, pkg_opts)?;
 			set_version(
 				gctx,
 				ws,
+				editor,
 				|p| predicate(p),
 				|p| {
 					let mut v = p.version().clone();
-					v.pre = Prerelease::new(&pre.clone()).expect("Static or expected to work");
+					v.pre = pre.clone();
 					Some(v)
 				},
 				force_update,
+				Cascade::No,
 			)
 		},
 		VersionCommand::SetBuild { meta, pkg_opts, force_update } => {
+			let meta = expand_git(ws.root(), &meta)?;
+			let meta = BuildMetadata::new(&meta)
+				.with_context(|| format!("Invalid build metadata `{}`", meta))?;
 			let predicate = make_pkg_predicate(gctx, ws, pkg_opts)?;
 			set_version(
 				gctx,
 				ws,
+				editor,
 				|p| predicate(p),
 				|p| {
 					let mut v = p.version().clone();
-					v.build = BuildMetadata::new(&meta.clone())
-						.expect("The meta you provided couldn't be parsed");
+					v.build = meta.clone();
 					Some(v)
 				},
 				force_update,
+				Cascade::No,
+			)
+		},
+		VersionCommand::Nightly { pkg_opts, force_update, pre } => {
+			let pre = expand_git(ws.root(), &pre)?;
+			let pre =
+				Prerelease::new(&pre).with_context(|| format!("Invalid pre-release `{}`", pre))?;
+			let predicate = make_pkg_predicate(gctx, ws, pkg_opts)?;
+			set_version(
+				gctx,
+				ws,
+				editor,
+				|p| predicate(p),
+				|p| {
+					let mut v = p.version().clone();
+					if v.pre.is_empty() {
+						bump_breaking_version(&mut v);
+					}
+					v.pre = pre.clone();
+					Some(v)
+				},
+				force_update,
+				Cascade::No,
 			)
 		},
 		VersionCommand::Release { pkg_opts, force_update } => {
@@ -353,6 +927,7 @@ pub fn adjust_version(
 			set_version(
 				gctx,
 				ws,
+				editor,
 				|p| predicate(p),
 				|p| {
 					let mut v = p.version().clone();
@@ -361,7 +936,63 @@ pub fn adjust_version(
 					Some(v)
 				},
 				force_update,
+				Cascade::No,
 			)
 		},
+		VersionCommand::Lint { fix } => lint_versions(gctx, ws, editor, fix),
+		VersionCommand::NormalizeReqs { style } => normalize_requirements(gctx, ws, editor, style),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn breaking_follows_caret_semantics() {
+		let v = |s: &str| Version::parse(s).unwrap();
+		assert!(is_breaking(&v("1.2.3"), &v("2.0.0")));
+		assert!(is_breaking(&v("0.3.1"), &v("0.4.0")));
+		assert!(is_breaking(&v("0.0.3"), &v("0.0.4")));
+		assert!(is_breaking(&v("0.3.1"), &v("0.4.0-dev")));
+		assert!(!is_breaking(&v("1.2.3"), &v("1.3.0")));
+		assert!(!is_breaking(&v("0.3.1"), &v("0.3.2")));
+		assert!(!is_breaking(&v("1.0.0-alpha.1"), &v("1.0.0-alpha.2")));
+	}
+
+	#[test]
+	fn pre_numbers_skip_published() {
+		let v = |s: &str| Version::parse(s).unwrap();
+		let published = [v("1.0.0-beta.1"), v("1.0.0-beta.3"), v("1.0.0-rc.1"), v("0.9.0-beta.7")];
+		assert_eq!(next_unused_pre(v("1.0.0-beta.2"), Some(&published)), v("1.0.0-beta.4"));
+		assert_eq!(next_unused_pre(v("1.0.0-beta.4"), Some(&published)), v("1.0.0-beta.4"));
+		assert_eq!(next_unused_pre(v("1.0.0-rc.1"), Some(&published)), v("1.0.0-rc.2"));
+		assert_eq!(next_unused_pre(v("1.0.0-alpha.1"), Some(&published)), v("1.0.0-alpha.1"));
+		assert_eq!(next_unused_pre(v("1.0.0-dev"), Some(&published)), v("1.0.0-dev"));
+		assert_eq!(next_unused_pre(v("1.0.0-beta.2"), None), v("1.0.0-beta.2"));
+	}
+
+	#[test]
+	fn promote_through_channels() {
+		let v = |s: &str| Version::parse(s).unwrap();
+		let channels = ["alpha", "beta", "rc"].map(|c| c.to_owned());
+		let promote = |from: &str, to| promote_version(&v(from), &channels, to);
+		assert_eq!(promote("1.0.0-alpha.3", Some("beta")).unwrap(), Some(v("1.0.0-beta.1")));
+		assert_eq!(promote("1.0.0-beta.2", Some("rc")).unwrap(), Some(v("1.0.0-rc.1")));
+		assert_eq!(promote("1.0.0-dev", Some("alpha")).unwrap(), Some(v("1.0.0-alpha.1")));
+		assert_eq!(promote("1.0.0-rc.2", None).unwrap(), Some(v("1.0.0")));
+		assert_eq!(promote("1.0.0-rc.2", Some("rc")).unwrap(), None);
+		assert_eq!(promote("1.0.0", Some("rc")).unwrap(), None);
+		assert!(promote("1.0.0-rc.1", Some("beta")).is_err());
+	}
+
+	#[test]
+	fn pub_use_detection() {
+		assert!(has_pub_use("pub use foo_bar::Type;", "foo_bar"));
+		assert!(has_pub_use("    pub use ::foo_bar;", "foo_bar"));
+		assert!(has_pub_use("pub extern crate foo_bar;", "foo_bar"));
+		assert!(!has_pub_use("pub use foo_bar_ext::Type;", "foo_bar"));
+		assert!(!has_pub_use("use foo_bar::Type;", "foo_bar"));
+		assert!(!has_pub_use("pub(crate) use foo_bar::Type;", "foo_bar"));
 	}
 }
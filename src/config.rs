@@ -0,0 +1,367 @@
+use anyhow::Context;
+use cargo::core::{package::Package, Workspace};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use semver::{Op, Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+
+use crate::cli::GenerateReadmeMode;
+
+/// Which files of a package are considered when detecting changes
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChangeMode {
+	/// Any file below the package root
+	#[default]
+	Files,
+	/// Only files that end up in the packaged `.crate`
+	Packaged,
+}
+
+/// How requirements on workspace members are written
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum RequirementStyle {
+	/// `"1.2.3"`
+	#[default]
+	Caret,
+	/// `"=1.2.3"`
+	Exact,
+	/// `"~1.2.3"`
+	Tilde,
+	/// `"1"`, or `"0.2"` and `"0.0.3"` for versions before 1.0
+	Major,
+	/// `"1.2"`, or `"0.2"` for versions before 1.0
+	Minor,
+}
+
+impl RequirementStyle {
+	/// The style of an existing requirement, anything unusual counts as caret
+	pub fn of(requirement: &str) -> RequirementStyle {
+		let req = match VersionReq::parse(requirement) {
+			Ok(req) => req,
+			Err(_) => return RequirementStyle::Caret,
+		};
+		match req.comparators.as_slice() {
+			[c] if c.op == Op::Exact => RequirementStyle::Exact,
+			[c] if c.op == Op::Tilde => RequirementStyle::Tilde,
+			[c] if c.op == Op::Caret && c.minor.is_none() => RequirementStyle::Major,
+			[c] if c.op == Op::Caret && c.patch.is_none() => RequirementStyle::Minor,
+			_ => RequirementStyle::Caret,
+		}
+	}
+
+	/// The requirement on `version` in this style. Pre-releases are always required in full.
+	pub fn requirement(&self, version: &Version) -> String {
+		match self {
+			RequirementStyle::Caret => version.to_string(),
+			RequirementStyle::Exact => format!("={}", version),
+			RequirementStyle::Tilde => format!("~{}", version),
+			RequirementStyle::Major | RequirementStyle::Minor if !version.pre.is_empty() =>
+				version.to_string(),
+			RequirementStyle::Major => match (version.major, version.minor) {
+				(0, 0) => format!("0.0.{}", version.patch),
+				(0, minor) => format!("0.{}", minor),
+				(major, _) => major.to_string(),
+			},
+			RequirementStyle::Minor => format!("{}.{}", version.major, version.minor),
+		}
+	}
+}
+
+/// Filters deciding which changed files make a package count as changed
+///
+/// Read from `[workspace.metadata.dragons.changes]`, any value set in
+/// `[package.metadata.dragons.changes]` takes precedence for that package.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct ChangesConfig {
+	/// Which set of files to consider at all
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub mode: Option<ChangeMode>,
+	/// Globs, relative to the package root, a changed file must match
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub include: Option<Vec<String>>,
+	/// Globs, relative to the package root, of changes to ignore
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub exclude: Option<Vec<String>>,
+}
+
+impl ChangesConfig {
+	/// Layer `other` on top of `self`, values set in `other` win
+	pub fn merge(&self, other: &ChangesConfig) -> ChangesConfig {
+		ChangesConfig {
+			mode: other.mode.or(self.mode),
+			include: other.include.clone().or_else(|| self.include.clone()),
+			exclude: other.exclude.clone().or_else(|| self.exclude.clone()),
+		}
+	}
+
+	/// Compile the globs into a filter
+	pub fn filter(&self) -> Result<ChangeFilter, anyhow::Error> {
+		Ok(ChangeFilter {
+			mode: self.mode.unwrap_or_default(),
+			include: self.include.as_deref().map(build_globset).transpose()?,
+			exclude: build_globset(self.exclude.as_deref().unwrap_or_default())?,
+		})
+	}
+}
+
+fn build_globset(globs: &[String]) -> Result<GlobSet, anyhow::Error> {
+	let mut builder = GlobSetBuilder::new();
+	for glob in globs {
+		builder.add(Glob::new(glob).with_context(|| format!("Invalid glob `{}`", glob))?);
+	}
+	builder.build().context("Building glob set failed")
+}
+
+/// Compiled form of [`ChangesConfig`]
+pub struct ChangeFilter {
+	pub mode: ChangeMode,
+	include: Option<GlobSet>,
+	exclude: GlobSet,
+}
+
+impl ChangeFilter {
+	/// Whether a change to `path`, relative to the package root, is relevant
+	pub fn is_relevant(&self, path: &Path) -> bool {
+		if self.exclude.is_match(path) {
+			return false;
+		}
+		self.include.as_ref().map(|include| include.is_match(path)).unwrap_or(true)
+	}
+}
+
+/// `[workspace.metadata.dragons]`
+///
+/// Defaults for the command line, any value given on the command line takes precedence.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct WorkspaceConfig {
+	/// Default for `--skip`
+	#[serde(default)]
+	pub skip: Vec<String>,
+	/// Default for `--ignore-pre-version`
+	#[serde(default)]
+	pub ignore_pre_version: Vec<String>,
+	/// Default for `--owner`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub owner: Option<String>,
+	/// Default for `--include-dev-deps`, `--no-include-dev-deps` overrides it
+	#[serde(default)]
+	pub include_dev_deps: bool,
+	/// Default for `--readme-mode`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub readme_mode: Option<GenerateReadmeMode>,
+	#[serde(default)]
+	pub changes: ChangesConfig,
+	/// Groups of packages, which always share the same version
+	#[serde(default)]
+	pub version_groups: BTreeMap<String, Vec<String>>,
+	/// Named invocations, run with `cargo dragons run <name>`
+	#[serde(default)]
+	pub profiles: BTreeMap<String, ProfileConfig>,
+	/// Style of requirements on members, keeps the style of each requirement if unset
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub requirement_style: Option<RequirementStyle>,
+	/// Order of the pre-release channels for `version promote`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub pre_release_channels: Option<Vec<String>>,
+	/// Lints members may set differently than `[workspace.lints]`, as `<tool>::<lint>`
+	#[serde(default)]
+	pub allowed_lint_overrides: Vec<String>,
+}
+
+/// `[workspace.metadata.dragons.profiles.<name>]`
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProfileConfig {
+	/// What the profile is used for
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+	/// The subcommand and its arguments, as given on the command line
+	pub args: Vec<String>,
+}
+
+/// `[package.metadata.dragons]`
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct PackageConfig {
+	/// Set to `false` to never select this package, unless `--ignore-publish` is given
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub release: Option<bool>,
+	/// Owners to add when publishing this package, in addition to `--owner`
+	#[serde(default)]
+	pub owners: Vec<String>,
+	/// Overrides the workspace `readme-mode`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub readme_mode: Option<GenerateReadmeMode>,
+	#[serde(default)]
+	pub changes: ChangesConfig,
+}
+
+impl PackageConfig {
+	/// Whether the package may be selected at all
+	pub fn release(&self) -> bool {
+		self.release.unwrap_or(true)
+	}
+}
+
+/// The configuration in effect for a package, after applying its overrides
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct EffectiveConfig {
+	pub skip: Vec<String>,
+	pub ignore_pre_version: Vec<String>,
+	pub include_dev_deps: bool,
+	pub release: bool,
+	pub owners: Vec<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub readme_mode: Option<GenerateReadmeMode>,
+	pub changes: ChangesConfig,
+}
+
+impl WorkspaceConfig {
+	/// The configured pre-release channels, `alpha`, `beta` and `rc` by default
+	pub fn pre_release_channels(&self) -> Vec<String> {
+		self.pre_release_channels
+			.clone()
+			.unwrap_or_else(|| Vec::from_iter(["alpha", "beta", "rc"].map(|c| c.to_owned())))
+	}
+
+	/// Apply the overrides of a package, if any
+	pub fn effective(&self, pkg: Option<&PackageConfig>) -> EffectiveConfig {
+		let pkg = pkg.cloned().unwrap_or_default();
+		EffectiveConfig {
+			skip: self.skip.clone(),
+			ignore_pre_version: self.ignore_pre_version.clone(),
+			include_dev_deps: self.include_dev_deps,
+			release: pkg.release(),
+			owners: Vec::from_iter(self.owner.iter().chain(pkg.owners.iter()).cloned()),
+			readme_mode: pkg.readme_mode.or(self.readme_mode),
+			changes: self.changes.merge(&pkg.changes),
+		}
+	}
+}
+
+fn from_metadata<T>(metadata: Option<&toml::Value>) -> Result<T, anyhow::Error>
+where
+	T: for<'de> Deserialize<'de> + Default,
+{
+	match metadata.and_then(|m| m.get("dragons")) {
+		Some(dragons) => Ok(dragons.clone().try_into()?),
+		None => Ok(T::default()),
+	}
+}
+
+/// Read the dragons configuration of the workspace
+pub fn workspace_config(ws: &Workspace<'_>) -> Result<WorkspaceConfig, anyhow::Error> {
+	from_metadata(ws.custom_metadata())
+		.context("Invalid `[workspace.metadata.dragons]` configuration")
+}
+
+/// Read the dragons configuration of a single package
+pub fn package_config(pkg: &Package) -> Result<PackageConfig, anyhow::Error> {
+	from_metadata(pkg.manifest().custom_metadata())
+		.with_context(|| format!("Invalid `[package.metadata.dragons]` in {}", pkg.name()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn package_changes_override_workspace() {
+		let ws: WorkspaceConfig = toml::from_str(
+			r#"
+[changes]
+mode = "packaged"
+exclude = ["README.md", "tests/**"]
+"#,
+		)
+		.unwrap();
+		let pkg: PackageConfig = toml::from_str(
+			r#"
+[changes]
+exclude = ["benches/**"]
+"#,
+		)
+		.unwrap();
+
+		let filter = ws.changes.merge(&pkg.changes).filter().unwrap();
+		assert_eq!(filter.mode, ChangeMode::Packaged);
+		assert!(filter.is_relevant(Path::new("README.md")));
+		assert!(filter.is_relevant(Path::new("src/lib.rs")));
+		assert!(!filter.is_relevant(Path::new("benches/foo.rs")));
+	}
+
+	#[test]
+	fn package_overrides_workspace_defaults() {
+		let ws: WorkspaceConfig = toml::from_str(
+			r#"
+skip = ["^internal-"]
+ignore-pre-version = ["dev"]
+owner = "github:org:release"
+readme-mode = "if-missing"
+"#,
+		)
+		.unwrap();
+		let pkg: PackageConfig = toml::from_str(
+			r#"
+release = false
+owners = ["someone"]
+readme-mode = "replace"
+"#,
+		)
+		.unwrap();
+
+		let effective = ws.effective(Some(&pkg));
+		assert!(!effective.release);
+		assert_eq!(effective.owners, vec!["github:org:release", "someone"]);
+		assert_eq!(effective.readme_mode, Some(GenerateReadmeMode::Replace));
+		assert_eq!(effective.ignore_pre_version, vec!["dev"]);
+
+		let effective = ws.effective(None);
+		assert!(effective.release);
+		assert_eq!(effective.readme_mode, Some(GenerateReadmeMode::IfMissing));
+	}
+
+	#[test]
+	fn include_restricts_changes() {
+		let changes = ChangesConfig {
+			include: Some(vec!["src/**".to_owned(), "Cargo.toml".to_owned()]),
+			exclude: Some(vec!["src/**/*.md".to_owned()]),
+			..Default::default()
+		};
+		let filter = changes.filter().unwrap();
+		assert!(filter.is_relevant(Path::new("Cargo.toml")));
+		assert!(filter.is_relevant(Path::new("src/a/b.rs")));
+		assert!(!filter.is_relevant(Path::new("src/a/notes.md")));
+		assert!(!filter.is_relevant(Path::new("tests/it.rs")));
+	}
+
+	#[test]
+	fn requirement_styles() {
+		let v = |s: &str| Version::parse(s).unwrap();
+		assert_eq!(RequirementStyle::Exact.requirement(&v("1.2.3")), "=1.2.3");
+		assert_eq!(RequirementStyle::Tilde.requirement(&v("1.2.3")), "~1.2.3");
+		assert_eq!(RequirementStyle::Major.requirement(&v("1.2.3")), "1");
+		assert_eq!(RequirementStyle::Major.requirement(&v("0.2.3")), "0.2");
+		assert_eq!(RequirementStyle::Major.requirement(&v("0.0.3")), "0.0.3");
+		assert_eq!(RequirementStyle::Major.requirement(&v("2.0.0-dev")), "2.0.0-dev");
+		assert_eq!(RequirementStyle::Minor.requirement(&v("1.3.0")), "1.3");
+		assert_eq!(RequirementStyle::Minor.requirement(&v("0.4.0")), "0.4");
+		assert_eq!(RequirementStyle::Minor.requirement(&v("1.3.0-rc.1")), "1.3.0-rc.1");
+
+		assert_eq!(RequirementStyle::of("=1.2.3"), RequirementStyle::Exact);
+		assert_eq!(RequirementStyle::of("~1.2"), RequirementStyle::Tilde);
+		assert_eq!(RequirementStyle::of("1"), RequirementStyle::Major);
+		assert_eq!(RequirementStyle::of("0"), RequirementStyle::Major);
+		// the precision is kept the same on both sides of 1.0
+		assert_eq!(RequirementStyle::of("0.2"), RequirementStyle::Minor);
+		assert_eq!(RequirementStyle::of("1.2"), RequirementStyle::Minor);
+		assert_eq!(RequirementStyle::of("0.0.3"), RequirementStyle::Caret);
+		assert_eq!(RequirementStyle::of("1.2.3"), RequirementStyle::Caret);
+		assert_eq!(RequirementStyle::of(">=1, <3"), RequirementStyle::Caret);
+	}
+}
@@ -0,0 +1,134 @@
+/// A line of a unified diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line<'a> {
+	/// `@@ -old_start,old_len +new_start,new_len @@`
+	Hunk(String),
+	Equal(&'a str),
+	Delete(&'a str),
+	Insert(&'a str),
+}
+
+impl Line<'_> {
+	fn is_change(&self) -> bool {
+		matches!(self, Line::Delete(_) | Line::Insert(_))
+	}
+}
+
+/// Line by line difference of `old` and `new`, based on their longest common subsequence
+fn changes<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+	// lcs[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+	let mut lcs = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+	for i in (0..old.len()).rev() {
+		for j in (0..new.len()).rev() {
+			lcs[i][j] = if old[i] == new[j] {
+				lcs[i + 1][j + 1] + 1
+			} else {
+				lcs[i + 1][j].max(lcs[i][j + 1])
+			};
+		}
+	}
+
+	let (mut i, mut j) = (0, 0);
+	let mut lines = Vec::with_capacity(old.len().max(new.len()));
+	while i < old.len() || j < new.len() {
+		if i < old.len() && j < new.len() && old[i] == new[j] {
+			lines.push(Line::Equal(old[i]));
+			i += 1;
+			j += 1;
+		} else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+			lines.push(Line::Delete(old[i]));
+			i += 1;
+		} else {
+			lines.push(Line::Insert(new[j]));
+			j += 1;
+		}
+	}
+	lines
+}
+
+/// The hunks of the unified diff from `old` to `new`, with `context` unchanged lines around
+/// each change. Empty if both are the same.
+pub fn unified<'a>(old: &'a str, new: &'a str, context: usize) -> Vec<Line<'a>> {
+	let old_lines = Vec::from_iter(old.lines());
+	let new_lines = Vec::from_iter(new.lines());
+	let changes = changes(&old_lines, &new_lines);
+
+	let mut hunks = Vec::new();
+	// line numbers before `start`, in `old` and `new`
+	let (mut old_line, mut new_line) = (0, 0);
+	let mut start = 0;
+	while let Some(first) = changes[start..].iter().position(Line::is_change).map(|p| p + start) {
+		let hunk_start = first.saturating_sub(context);
+		// changes closer than twice the context share a hunk
+		let mut last = first;
+		for (index, line) in changes.iter().enumerate().skip(first) {
+			if line.is_change() {
+				last = index;
+			} else if index - last > 2 * context {
+				break
+			}
+		}
+		let hunk_end = (last + context + 1).min(changes.len());
+
+		for line in changes[start..hunk_start].iter() {
+			old_line += !matches!(line, Line::Insert(_)) as usize;
+			new_line += !matches!(line, Line::Delete(_)) as usize;
+		}
+		let hunk = &changes[hunk_start..hunk_end];
+		let old_len = hunk.iter().filter(|l| !matches!(l, Line::Insert(_))).count();
+		let new_len = hunk.iter().filter(|l| !matches!(l, Line::Delete(_))).count();
+		// empty ranges start at the line before, like `diff -u` does
+		let from = |line: usize, len: usize| if len == 0 { line } else { line + 1 };
+		hunks.push(Line::Hunk(format!(
+			"@@ -{},{} +{},{} @@",
+			from(old_line, old_len),
+			old_len,
+			from(new_line, new_len),
+			new_len
+		)));
+		hunks.extend(hunk.iter().cloned());
+		old_line += old_len;
+		new_line += new_len;
+		start = hunk_end;
+	}
+	hunks
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn unchanged() {
+		assert!(unified("a\nb\n", "a\nb\n", 3).is_empty());
+	}
+
+	#[test]
+	fn hunks_with_context() {
+		let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+		let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+		assert_eq!(
+			unified(old, new, 1),
+			vec![
+				Line::Hunk("@@ -1,3 +1,3 @@".to_owned()),
+				Line::Equal("a"),
+				Line::Delete("b"),
+				Line::Insert("B"),
+				Line::Equal("c"),
+				Line::Hunk("@@ -10,1 +10,2 @@".to_owned()),
+				Line::Equal("j"),
+				Line::Insert("k"),
+			]
+		);
+		// close changes share a hunk
+		assert_eq!(unified(old, new, 4).iter().filter(|l| matches!(l, Line::Hunk(_))).count(), 1);
+	}
+
+	#[test]
+	fn into_empty() {
+		assert_eq!(
+			unified("", "a\n", 3),
+			vec![Line::Hunk("@@ -0,0 +1,1 @@".to_owned()), Line::Insert("a")]
+		);
+	}
+}
@@ -0,0 +1,474 @@
+use crate::diff::{unified, Line};
+use anyhow::Context;
+use cargo::core::Workspace;
+use cargo_util_schemas::manifest::TomlManifest;
+use serde::{Deserialize, Serialize};
+use std::{
+	cell::RefCell,
+	collections::BTreeMap,
+	fs,
+	io::Write,
+	path::{Path, PathBuf},
+	time::{SystemTime, UNIX_EPOCH},
+};
+use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+/// A manifest touched by a run, with its content before and after
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JournalEntry {
+	pub path: PathBuf,
+	/// `None` if the file didn't exist before
+	pub original: Option<String>,
+	pub written: String,
+}
+
+/// A directory moved by a run, before its files were written
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JournalMove {
+	pub from: PathBuf,
+	pub to: PathBuf,
+}
+
+/// The manifests one run of a mutating command changed
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Journal {
+	pub id: String,
+	/// The command line of the run
+	pub command: String,
+	pub files: Vec<JournalEntry>,
+	/// The directories moved, in order
+	#[serde(default)]
+	pub moves: Vec<JournalMove>,
+}
+
+const JOURNAL_FILE: &str = "journal.json";
+
+/// The directory all journals of the workspace are kept in: `target/dragons/journal`
+fn journals_dir(ws: &Workspace<'_>) -> PathBuf {
+	ws.target_dir().into_path_unlocked().join("dragons").join("journal")
+}
+
+/// Edits manifests all-or-nothing, keeping their original content in a journal, so the run can
+/// be undone with `cargo dragons undo`.
+///
+/// Edits are staged in memory and only written by `commit`, once all of them are done and every
+/// staged manifest is valid. On dry runs, `commit` prints them as unified diffs instead. The
+/// journal is created by the first commit writing anything, runs not changing anything don't leave
+/// one behind.
+pub struct ManifestEditor {
+	root: PathBuf,
+	dir: PathBuf,
+	dry_run: bool,
+	staged: RefCell<BTreeMap<PathBuf, String>>,
+	/// Directories to move, `(from, to)`
+	moves: RefCell<Vec<(PathBuf, PathBuf)>>,
+	journal: RefCell<Journal>,
+}
+
+/// Where the file at `path` is before the `moves` are done, `None` if it's to be written to a
+/// directory moved away
+fn moved_from(moves: &[(PathBuf, PathBuf)], path: &Path) -> Option<PathBuf> {
+	let mut path = path.to_path_buf();
+	for (from, to) in moves.iter().rev() {
+		if let Ok(rest) = path.strip_prefix(to) {
+			path = from.join(rest);
+		} else if path.starts_with(from) {
+			return None
+		}
+	}
+	Some(path)
+}
+
+/// Put back the `original` content of the file at `path`. Files that didn't exist are removed,
+/// together with the directories left empty.
+fn restore_file(path: &Path, original: Option<&String>) -> std::io::Result<()> {
+	let Some(original) = original else {
+		fs::remove_file(path)?;
+		let mut dir = path.parent();
+		while let Some(empty) = dir.filter(|d| fs::remove_dir(d).is_ok()) {
+			dir = empty.parent();
+		}
+		return Ok(())
+	};
+	fs::write(path, original)
+}
+
+impl ManifestEditor {
+	pub fn new(ws: &Workspace<'_>, dry_run: bool) -> Result<ManifestEditor, anyhow::Error> {
+		let journals = journals_dir(ws);
+		let secs = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+		// more than one run per second, e.g. from a profile
+		let mut id = secs.to_string();
+		let mut counter = 0;
+		while journals.join(&id).exists() {
+			counter += 1;
+			id = format!("{}-{}", secs, counter);
+		}
+		let command = Vec::from_iter(std::env::args().skip(1)).join(" ");
+		Ok(ManifestEditor {
+			root: ws.root().to_path_buf(),
+			dir: journals.join(&id),
+			dry_run,
+			staged: RefCell::new(BTreeMap::new()),
+			moves: RefCell::new(Vec::new()),
+			journal: RefCell::new(Journal { id, command, files: Vec::new(), moves: Vec::new() }),
+		})
+	}
+
+	/// The content of the manifest at `path`, including staged edits and moves
+	pub fn read(&self, path: &Path) -> Result<String, anyhow::Error> {
+		if let Some(content) = self.staged.borrow().get(path) {
+			return Ok(content.clone())
+		}
+		moved_from(&self.moves.borrow(), path)
+			.ok_or_else(|| anyhow::anyhow!("{} is moved away", path.display()))
+			.and_then(|from| Ok(fs::read_to_string(from)?))
+			.with_context(|| format!("Can't read {}", path.display()))
+	}
+
+	/// Stage `content` for the manifest, or other file like a source, at `path`, written by
+	/// `commit`
+	pub fn write(&self, path: &Path, content: String) -> Result<(), anyhow::Error> {
+		self.staged.borrow_mut().insert(path.to_path_buf(), content);
+		Ok(())
+	}
+
+	/// Stage moving the directory `from` to `to`, done by `commit` before writing the staged
+	/// files. Files staged below `from` so far move along, those staged there afterwards are
+	/// written to the emptied directory.
+	pub fn move_dir(&self, from: &Path, to: &Path) -> Result<(), anyhow::Error> {
+		let mut staged = self.staged.borrow_mut();
+		let moving = Vec::from_iter(staged.keys().filter(|p| p.starts_with(from)).cloned());
+		for path in moving {
+			let content = staged.remove(&path).expect("Listed above. qed");
+			let rest = path.strip_prefix(from).expect("Filtered above. qed");
+			staged.insert(to.join(rest), content);
+		}
+		self.moves.borrow_mut().push((from.to_path_buf(), to.to_path_buf()));
+		Ok(())
+	}
+
+	pub fn is_dry_run(&self) -> bool {
+		self.dry_run
+	}
+
+	/// Validate and write all staged manifests, recording their original content in the journal.
+	/// On dry runs, print the changes as unified diffs instead.
+	///
+	/// Nothing is written if any staged manifest doesn't load as a cargo manifest. If writing
+	/// fails, the manifests written so far are restored and the directories moved back.
+	pub fn commit(&self) -> Result<(), anyhow::Error> {
+		let staged = self.validated()?;
+		if self.dry_run {
+			self.print_diffs(&staged)
+		} else {
+			self.write_all(staged)
+		}
+	}
+
+	/// Validate and write all staged manifests, even on dry runs
+	pub fn apply(&self) -> Result<(), anyhow::Error> {
+		let staged = self.validated()?;
+		self.write_all(staged)
+	}
+
+	/// Take the staged files, failing if any of the manifests among them isn't valid
+	fn validated(&self) -> Result<BTreeMap<PathBuf, String>, anyhow::Error> {
+		let staged = std::mem::take(&mut *self.staged.borrow_mut());
+		for (path, content) in
+			staged.iter().filter(|(path, _)| path.extension().is_some_and(|e| e == "toml"))
+		{
+			toml::from_str::<TomlManifest>(content).with_context(|| {
+				format!("Aborting: edited {} isn't a valid manifest", path.display())
+			})?;
+		}
+		Ok(staged)
+	}
+
+	fn print_diffs(&self, staged: &BTreeMap<PathBuf, String>) -> Result<(), anyhow::Error> {
+		let moves = std::mem::take(&mut *self.moves.borrow_mut());
+		if staged.is_empty() && moves.is_empty() {
+			return Ok(())
+		}
+		let mut out = StandardStream::stdout(ColorChoice::Auto);
+		let name =
+			|path: &Path| path.strip_prefix(&self.root).unwrap_or(path).display().to_string();
+		for (from, to) in moves.iter() {
+			out.set_color(ColorSpec::new().set_bold(true))?;
+			writeln!(out, "rename {} => {}", name(from), name(to))?;
+			out.reset()?;
+		}
+		let (mut changed, mut insertions, mut deletions) = (0, 0, 0);
+		for (path, content) in staged.iter() {
+			let from = moved_from(&moves, path);
+			let original =
+				from.as_ref().and_then(|from| fs::read_to_string(from).ok()).unwrap_or_default();
+			let lines = unified(&original, content, 3);
+			if lines.is_empty() {
+				continue
+			}
+			changed += 1;
+			out.set_color(ColorSpec::new().set_bold(true))?;
+			let from = from.as_deref().map(name).unwrap_or_else(|| name(path));
+			writeln!(out, "--- a/{}\n+++ b/{}", from, name(path))?;
+			for line in lines {
+				let (color, prefix, text) = match line {
+					Line::Hunk(ref header) => (Some(Color::Cyan), "", header.as_str()),
+					Line::Equal(text) => (None, " ", text),
+					Line::Delete(text) => {
+						deletions += 1;
+						(Some(Color::Red), "-", text)
+					},
+					Line::Insert(text) => {
+						insertions += 1;
+						(Some(Color::Green), "+", text)
+					},
+				};
+				out.set_color(ColorSpec::new().set_fg(color))?;
+				writeln!(out, "{}{}", prefix, text)?;
+			}
+			out.reset()?;
+		}
+		writeln!(
+			out,
+			"{} manifests changed, {} insertions(+), {} deletions(-) (dry run, nothing written)",
+			changed, insertions, deletions
+		)?;
+		Ok(())
+	}
+
+	fn write_all(&self, staged: BTreeMap<PathBuf, String>) -> Result<(), anyhow::Error> {
+		let moves = std::mem::take(&mut *self.moves.borrow_mut());
+		if staged.is_empty() && moves.is_empty() {
+			return Ok(())
+		}
+
+		// the content before this commit, to roll back to
+		let mut before = BTreeMap::new();
+		for path in staged.keys() {
+			let content = match moved_from(&moves, path).map(fs::read_to_string) {
+				Some(Ok(content)) => Some(content),
+				Some(Err(e)) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+				_ => None,
+			};
+			before.insert(path, content);
+		}
+
+		let mut journal = self.journal.borrow_mut();
+		let previous = journal.clone();
+		for (path, content) in staged.iter() {
+			match journal.files.iter_mut().find(|f| &f.path == path) {
+				Some(file) => file.written.clone_from(content),
+				None => journal.files.push(JournalEntry {
+					path: path.clone(),
+					original: before[path].clone(),
+					written: content.clone(),
+				}),
+			}
+		}
+		journal.moves.extend(
+			moves
+				.iter()
+				.map(|(from, to)| JournalMove { from: from.clone(), to: to.clone() }),
+		);
+
+		// the journal is written before the manifests, so an aborted run can be undone as well
+		fs::create_dir_all(&self.dir)
+			.with_context(|| format!("Can't create journal {}", self.dir.display()))?;
+		fs::write(self.dir.join(JOURNAL_FILE), serde_json::to_string_pretty(&*journal)?)?;
+
+		let mut moved = Vec::new();
+		let mut written = Vec::new();
+		let result = (|| -> Result<(), anyhow::Error> {
+			for (from, to) in moves.iter() {
+				fs::rename(from, to).with_context(|| {
+					format!("Can't move {} to {}", from.display(), to.display())
+				})?;
+				moved.push((from, to));
+			}
+			for (path, content) in staged.iter() {
+				// files of new packages, or in a directory moved away, are written to new
+				// directories
+				if let Some(dir) = path.parent() {
+					fs::create_dir_all(dir)
+						.with_context(|| format!("Can't create {}", dir.display()))?;
+				}
+				fs::write(path, content)
+					.with_context(|| format!("Can't write {}", path.display()))?;
+				written.push(path);
+			}
+			Ok(())
+		})();
+
+		if let Err(e) = result {
+			for path in written.iter().rev() {
+				let _ = restore_file(path, before[path].as_ref());
+			}
+			for (from, to) in moved.iter().rev() {
+				let _ = fs::rename(to, from);
+			}
+			*journal = previous;
+			let _ = if journal.files.is_empty() && journal.moves.is_empty() {
+				fs::remove_dir_all(&self.dir)
+			} else {
+				fs::write(self.dir.join(JOURNAL_FILE), serde_json::to_string_pretty(&*journal)?)
+			};
+			return Err(e.context(format!(
+				"Restored the {} manifests written and {} directories moved before",
+				written.len(),
+				moved.len()
+			)))
+		}
+		Ok(())
+	}
+}
+
+/// All journals of the workspace, the latest first
+pub fn journals(ws: &Workspace<'_>) -> Result<Vec<Journal>, anyhow::Error> {
+	let dir = journals_dir(ws);
+	if !dir.is_dir() {
+		return Ok(Vec::new());
+	}
+	let mut journals = Vec::new();
+	for entry in fs::read_dir(&dir)? {
+		let path = entry?.path().join(JOURNAL_FILE);
+		if !path.is_file() {
+			continue;
+		}
+		let content = fs::read_to_string(&path)?;
+		journals.push(
+			serde_json::from_str::<Journal>(&content)
+				.with_context(|| format!("Invalid journal {}", path.display()))?,
+		);
+	}
+	journals.sort_by_key(|j| std::cmp::Reverse(sort_key(&j.id)));
+	Ok(journals)
+}
+
+/// Ids are `<seconds>` or `<seconds>-<counter>`
+fn sort_key(id: &str) -> (u64, u64) {
+	let (secs, counter) = id.split_once('-').unwrap_or((id, "0"));
+	(secs.parse().unwrap_or_default(), counter.parse().unwrap_or_default())
+}
+
+impl Journal {
+	/// Manifests changed since this run wrote them
+	pub fn modified_since(&self) -> Vec<&Path> {
+		Vec::from_iter(
+			self.files
+				.iter()
+				.filter(|f| fs::read_to_string(&f.path).ok().as_ref() != Some(&f.written))
+				.map(|f| f.path.as_path()),
+		)
+	}
+
+	/// Put back the original content of all manifests, move the moved directories back and
+	/// remove the journal
+	pub fn restore(&self, ws: &Workspace<'_>) -> Result<(), anyhow::Error> {
+		for file in self.files.iter() {
+			restore_file(&file.path, file.original.as_ref())
+				.with_context(|| format!("Can't restore {}", file.path.display()))?;
+		}
+		for JournalMove { from, to } in self.moves.iter().rev() {
+			fs::rename(to, from).with_context(|| {
+				format!("Can't move {} back to {}", to.display(), from.display())
+			})?;
+		}
+		fs::remove_dir_all(journals_dir(ws).join(&self.id))?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn ids_sort_by_time_and_counter() {
+		let mut ids = vec!["1700000000-2", "1700000001", "1700000000", "1700000000-10"];
+		ids.sort_by_key(|id| sort_key(id));
+		assert_eq!(ids, vec!["1700000000", "1700000000-2", "1700000000-10", "1700000001"]);
+	}
+
+	fn editor(root: &Path) -> ManifestEditor {
+		ManifestEditor {
+			root: root.to_path_buf(),
+			dir: root.join("journal"),
+			dry_run: false,
+			staged: RefCell::new(BTreeMap::new()),
+			moves: RefCell::new(Vec::new()),
+			journal: RefCell::new(Journal {
+				id: "1".to_owned(),
+				command: "test".to_owned(),
+				files: Vec::new(),
+				moves: Vec::new(),
+			}),
+		}
+	}
+
+	#[test]
+	fn commits_all_or_nothing() {
+		let tmp = tempdir::TempDir::new("editor").expect("Can create temp dir");
+		let a = tmp.path().join("a.toml");
+		let b = tmp.path().join("b.toml");
+		let original = "[package]\nname = \"a\"\nversion = \"0.1.0\"\n";
+		fs::write(&a, original).unwrap();
+		fs::write(&b, original.replace("\"a\"", "\"b\"")).unwrap();
+
+		let editor = editor(tmp.path());
+		let bumped = original.replace("0.1.0", "0.2.0");
+		editor.write(&a, bumped.clone()).unwrap();
+		assert_eq!(editor.read(&a).unwrap(), bumped);
+		editor.write(&b, "[package]\nname = [\"b\"]\n".to_owned()).unwrap();
+		assert!(editor.commit().is_err());
+		assert_eq!(fs::read_to_string(&a).unwrap(), original);
+		assert!(!editor.dir.exists());
+
+		editor.write(&a, bumped.clone()).unwrap();
+		editor.commit().unwrap();
+		assert_eq!(fs::read_to_string(&a).unwrap(), bumped);
+		let journal = editor.journal.borrow();
+		assert_eq!(journal.files.len(), 1);
+		assert_eq!(journal.files[0].original.as_deref(), Some(original));
+	}
+
+	#[test]
+	fn moves_directories_along_with_staged_files() {
+		let tmp = tempdir::TempDir::new("editor").expect("Can create temp dir");
+		let (old, new) = (tmp.path().join("old"), tmp.path().join("new"));
+		fs::create_dir_all(old.join("src")).unwrap();
+		fs::write(old.join("src").join("lib.rs"), "").unwrap();
+		let original = "[package]\nname = \"a\"\nversion = \"0.1.0\"\n";
+		fs::write(old.join("Cargo.toml"), original).unwrap();
+		let renamed = original.replace("\"a\"", "\"b\"");
+
+		let editor = editor(tmp.path());
+		editor.write(&old.join("Cargo.toml"), renamed.clone()).unwrap();
+		editor.move_dir(&old, &new).unwrap();
+		assert_eq!(editor.read(&new.join("Cargo.toml")).unwrap(), renamed);
+		// a file in the emptied directory, which can't be written
+		fs::write(tmp.path().join("blocker"), "").unwrap();
+		editor
+			.write(&tmp.path().join("blocker").join("Cargo.toml"), original.to_owned())
+			.unwrap();
+		assert!(editor.commit().is_err());
+		assert_eq!(fs::read_to_string(old.join("Cargo.toml")).unwrap(), original);
+		assert!(old.join("src").join("lib.rs").exists());
+		assert!(!new.exists());
+
+		editor.write(&old.join("Cargo.toml"), renamed.clone()).unwrap();
+		editor.move_dir(&old, &new).unwrap();
+		editor.write(&old.join("Cargo.toml"), original.to_owned()).unwrap();
+		editor.commit().unwrap();
+		assert_eq!(fs::read_to_string(new.join("Cargo.toml")).unwrap(), renamed);
+		assert!(new.join("src").join("lib.rs").exists());
+		assert_eq!(fs::read_to_string(old.join("Cargo.toml")).unwrap(), original);
+		let journal = editor.journal.borrow();
+		assert_eq!(journal.moves.len(), 1);
+		let original_of = |path: &Path| {
+			journal.files.iter().find(|f| f.path == path).and_then(|f| f.original.clone())
+		};
+		assert_eq!(original_of(&new.join("Cargo.toml")).as_deref(), Some(original));
+		assert_eq!(original_of(&old.join("Cargo.toml")), None);
+	}
+}
@@ -5,6 +5,11 @@
 use clap::Parser;
 mod cli;
 mod commands;
+mod config;
+mod diff;
+mod editor;
+mod registry;
+mod template;
 mod util;
 
 #[cfg(test)]
@@ -0,0 +1,260 @@
+use anyhow::Context;
+use cargo::{
+	core::{package::Package, resolver::CliFeatures, Dependency, SourceId, Workspace},
+	ops::{self, PackageOpts},
+	sources::source::{QueryKind, Source},
+	util::{cache_lock::CacheLockMode, interning::InternedString},
+	GlobalContext,
+};
+use flate2::read::GzDecoder;
+use log::{trace, warn};
+use semver::Version;
+use std::{
+	collections::{BTreeMap, HashMap, HashSet},
+	fs::{self, File},
+	io::Read,
+	path::{Path, PathBuf},
+	task::Poll,
+};
+use tar::Archive;
+
+use crate::util::members_deep;
+
+/// Files of a `.crate`, relative to the package root, with normalized contents
+type CrateContents = BTreeMap<PathBuf, Vec<u8>>;
+
+/// Files cargo generates while packaging, which differ between cargo versions and checkouts
+/// even if the sources didn't change. `Cargo.toml.orig` is compared instead of the normalized
+/// `Cargo.toml`, while a packaged `Cargo.lock` pins the dependencies of binaries and is compared.
+const GENERATED_FILES: &[&str] = &[".cargo_vcs_info.json", "Cargo.toml"];
+
+/// How the local sources of a package relate to the published `.crate` of the same version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryState {
+	/// The sources are identical to the published ones
+	Unchanged,
+	/// The sources differ in the listed files, but the version wasn't bumped
+	Changed(Vec<PathBuf>),
+	/// No published `.crate` of this version was found locally
+	Unknown,
+}
+
+/// Query the index of the main registry (usually crates.io) for all versions published of the
+/// given crates, including pre-releases but not yanked versions.
+pub fn published_versions<I>(
+	gctx: &GlobalContext,
+	names: I,
+) -> Result<HashMap<InternedString, Vec<Version>>, anyhow::Error>
+where
+	I: IntoIterator<Item = InternedString>,
+{
+	gctx.shell().status("Syncing", "Versions from crates.io")?;
+
+	let source_id = SourceId::crates_io_maybe_sparse_http(gctx).context(
+		"Your main registry (usually crates.io) can't be read. Please check your .cargo/config",
+	)?;
+	let _lock = gctx.acquire_package_cache_lock(CacheLockMode::DownloadExclusive)?;
+	let mut registry = source_id
+		.load(gctx, &Default::default())
+		.context("Failed getting remote registry")?;
+
+	registry.invalidate_cache();
+
+	let mut published = HashMap::new();
+	for name in names {
+		if published.contains_key(&name) {
+			continue;
+		}
+		// without a requirement, pre-releases match as well
+		let dep = Dependency::parse(name, None, registry.source_id())?;
+		let mut versions = Vec::new();
+		loop {
+			versions.clear();
+			match registry.query(&dep, QueryKind::Exact, &mut |summary| {
+				versions.push(summary.as_summary().version().clone())
+			}) {
+				Poll::Ready(result) => {
+					result.with_context(|| format!("Querying the registry for {} failed", name))?;
+					break;
+				},
+				Poll::Pending => registry.block_until_ready()?,
+			}
+		}
+		versions.sort();
+		published.insert(name, versions);
+	}
+	Ok(published)
+}
+
+/// Find the published `.crate` of the given package in the local cargo registry cache
+/// or in one of the given stand-in directories.
+fn find_published_crate(
+	gctx: &GlobalContext,
+	extra_dirs: &[PathBuf],
+	pkg: &Package,
+) -> Result<Option<PathBuf>, anyhow::Error> {
+	let file_name = format!("{}-{}.crate", pkg.name(), pkg.version());
+
+	// the cache contains one folder per registry, e.g. `index.crates.io-6f17d22bba15001f`
+	let cache = gctx.registry_cache_path().into_path_unlocked();
+	let mut dirs = Vec::from_iter(extra_dirs.iter().cloned());
+	if cache.is_dir() {
+		for entry in fs::read_dir(&cache)? {
+			let entry = entry?;
+			if entry.file_type()?.is_dir() {
+				dirs.push(entry.path());
+			}
+		}
+	}
+
+	Ok(dirs
+		.into_iter()
+		.map(|d| d.join(&file_name))
+		.find(|candidate| candidate.is_file()))
+}
+
+/// Read the content of a `.crate` file, normalizing line endings and skipping the
+/// files cargo generates while packaging.
+fn crate_contents(path: &Path) -> Result<CrateContents, anyhow::Error> {
+	let file = File::open(path).with_context(|| format!("Can't open {}", path.display()))?;
+	let mut archive = Archive::new(GzDecoder::new(file));
+	let mut contents = CrateContents::new();
+	for entry in archive.entries()? {
+		let mut entry = entry?;
+		if !entry.header().entry_type().is_file() {
+			continue;
+		}
+		// strip the `name-version/` prefix
+		let relative = PathBuf::from_iter(entry.path()?.components().skip(1));
+		if GENERATED_FILES.iter().any(|generated| relative == Path::new(generated)) {
+			continue;
+		}
+		let mut content = Vec::new();
+		entry.read_to_end(&mut content)?;
+		contents.insert(relative, normalize_line_endings(content));
+	}
+	Ok(contents)
+}
+
+fn normalize_line_endings(content: Vec<u8>) -> Vec<u8> {
+	if !content.contains(&b'\r') {
+		return content;
+	}
+	let mut normalized = Vec::with_capacity(content.len());
+	let mut iter = content.iter().peekable();
+	while let Some(&byte) = iter.next() {
+		if byte == b'\r' && iter.peek() == Some(&&b'\n') {
+			continue;
+		}
+		normalized.push(byte);
+	}
+	normalized
+}
+
+/// The files that were added, removed or differ between two crate contents
+fn differing_files(local: &CrateContents, published: &CrateContents) -> Vec<PathBuf> {
+	let paths = HashSet::<&PathBuf>::from_iter(local.keys().chain(published.keys()));
+	let mut changed = Vec::from_iter(
+		paths
+			.into_iter()
+			.filter(|path| local.get(*path) != published.get(*path))
+			.cloned(),
+	);
+	changed.sort();
+	changed
+}
+
+/// Package `pkg` and compare the result with the `.crate` published under the same version.
+pub fn registry_state(
+	gctx: &GlobalContext,
+	ws: &Workspace<'_>,
+	pkg: &Package,
+	extra_dirs: &[PathBuf],
+) -> Result<RegistryState, anyhow::Error> {
+	let published = match find_published_crate(gctx, extra_dirs, pkg)? {
+		Some(published) => published,
+		None => return Ok(RegistryState::Unknown),
+	};
+	trace!("Comparing {} against {}", pkg.name(), published.display());
+
+	let opts = PackageOpts {
+		gctx,
+		verify: false,
+		check_metadata: false,
+		list: false,
+		allow_dirty: true,
+		jobs: None,
+		to_package: ops::Packages::Default,
+		targets: Default::default(),
+		cli_features: CliFeatures {
+			features: Default::default(),
+			all_features: false,
+			uses_default_features: true,
+		},
+		keep_going: false,
+	};
+	let local = ops::package_one(ws, pkg, &opts)
+		.with_context(|| format!("Packaging {} failed", pkg.name()))?;
+
+	let changed = differing_files(&crate_contents(local.path())?, &crate_contents(&published)?);
+	Ok(if changed.is_empty() { RegistryState::Unchanged } else { RegistryState::Changed(changed) })
+}
+
+/// Find all packages matching `predicate`, whose sources differ from what was published under
+/// the same version. Packages not matching aren't packaged at all.
+pub fn changed_vs_registry<P>(
+	gctx: &GlobalContext,
+	ws: &Workspace<'_>,
+	extra_dirs: &[PathBuf],
+	predicate: P,
+) -> Result<HashSet<Package>, anyhow::Error>
+where
+	P: Fn(&Package) -> bool,
+{
+	gctx.shell()
+		.status("Comparing", "Packages with published versions")
+		.expect("Writing to Shell doesn't fail");
+
+	let mut packages = HashSet::new();
+	for m in members_deep(gctx, ws).into_iter().filter(|m| predicate(m)) {
+		if packages.contains(&m) {
+			continue;
+		}
+		match registry_state(gctx, ws, &m, extra_dirs) {
+			Ok(RegistryState::Changed(files)) => {
+				trace!("{} changed without a version bump: {:?}", m.name(), files);
+				packages.insert(m);
+			},
+			Ok(_) => {},
+			Err(e) => warn!("Can't compare {} with the registry: {:?}", m.name(), e),
+		}
+	}
+	Ok(packages)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn line_endings_are_normalized() {
+		assert_eq!(normalize_line_endings(b"a\r\nb\rc\n".to_vec()), b"a\nb\rc\n".to_vec());
+	}
+
+	#[test]
+	fn differing_files_are_sorted() {
+		let local = CrateContents::from_iter([
+			(PathBuf::from("src/lib.rs"), b"new".to_vec()),
+			(PathBuf::from("Cargo.toml.orig"), b"same".to_vec()),
+			(PathBuf::from("src/added.rs"), b"".to_vec()),
+		]);
+		let published = CrateContents::from_iter([
+			(PathBuf::from("src/lib.rs"), b"old".to_vec()),
+			(PathBuf::from("Cargo.toml.orig"), b"same".to_vec()),
+		]);
+		assert_eq!(
+			differing_files(&local, &published),
+			vec![PathBuf::from("src/added.rs"), PathBuf::from("src/lib.rs")]
+		);
+	}
+}
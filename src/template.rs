@@ -0,0 +1,143 @@
+use anyhow::Context;
+use git2::Repository;
+use std::{
+	path::Path,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Replace each `{name}` in `template` by the value `lookup` returns for it. `{{` and `}}` stand
+/// for literal braces, while unknown placeholders and other braces, like those of inline
+/// tables, are kept as they are.
+pub fn expand<F>(template: &str, mut lookup: F) -> Result<String, anyhow::Error>
+where
+	F: FnMut(&str) -> Result<Option<String>, anyhow::Error>,
+{
+	let is_name = |name: &str| {
+		!name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+	};
+	let mut expanded = String::with_capacity(template.len());
+	let mut rest = template;
+	while let Some(start) = rest.find(['{', '}']) {
+		expanded.push_str(&rest[..start]);
+		rest = &rest[start..];
+		if rest.starts_with("{{") || rest.starts_with("}}") {
+			expanded.push_str(&rest[..1]);
+			rest = &rest[2..];
+			continue
+		}
+		let placeholder = match rest.find('}') {
+			Some(end) if rest.starts_with('{') && is_name(&rest[1..end]) =>
+				lookup(&rest[1..end])?.map(|value| (value, end)),
+			_ => None,
+		};
+		match placeholder {
+			Some((value, end)) => {
+				expanded.push_str(&value);
+				rest = &rest[end + 1..];
+			},
+			None => {
+				expanded.push_str(&rest[..1]);
+				rest = &rest[1..];
+			},
+		}
+	}
+	expanded.push_str(rest);
+	Ok(expanded)
+}
+
+/// Fill in `{date}`, `{git_sha}` and `{commit_count_since_tag}` from the git repository
+/// containing `root`.
+///
+/// The date is today's in UTC as `YYYYMMDD`, or the one of `SOURCE_DATE_EPOCH` if set.
+pub fn expand_git(root: &Path, template: &str) -> Result<String, anyhow::Error> {
+	let mut repo = None;
+	expand(template, |name| {
+		let value = match name {
+			"date" => today()?,
+			"git_sha" | "commit_count_since_tag" => {
+				if repo.is_none() {
+					repo = Some(Repository::discover(root).context("Workspace isn't a git repo")?);
+				}
+				let repo = repo.as_ref().expect("Set just before. qed");
+				if name == "git_sha" {
+					short_sha(repo)?
+				} else {
+					commits_since_tag(repo)?.to_string()
+				}
+			},
+			_ => return Ok(None),
+		};
+		Ok(Some(value))
+	})
+}
+
+fn short_sha(repo: &Repository) -> Result<String, anyhow::Error> {
+	let head = repo
+		.head()
+		.and_then(|h| h.peel_to_commit())
+		.context("Could not determine git HEAD")?;
+	let short = head.as_object().short_id()?;
+	Ok(short.as_str().expect("Object ids are hex. qed").to_owned())
+}
+
+/// The number of commits reachable from `HEAD` but not from any tag
+fn commits_since_tag(repo: &Repository) -> Result<usize, anyhow::Error> {
+	let mut walk = repo.revwalk()?;
+	walk.push_head().context("Could not determine git HEAD")?;
+	for name in repo.tag_names(None)?.iter().flatten() {
+		if let Ok(commit) = repo
+			.revparse_single(&format!("refs/tags/{}", name))
+			.and_then(|o| o.peel_to_commit())
+		{
+			walk.hide(commit.id())?;
+		}
+	}
+	Ok(walk.count())
+}
+
+fn today() -> Result<String, anyhow::Error> {
+	let seconds = match std::env::var("SOURCE_DATE_EPOCH") {
+		Ok(epoch) => epoch.parse::<i64>().context("Invalid `SOURCE_DATE_EPOCH`")?,
+		Err(_) => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64,
+	};
+	let (year, month, day) = civil_from_days(seconds.div_euclid(86400));
+	Ok(format!("{:04}{:02}{:02}", year, month, day))
+}
+
+/// The date of the given number of days since 1970-01-01, see
+/// <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+	let z = days + 719468;
+	let era = z.div_euclid(146097);
+	let doe = z.rem_euclid(146097);
+	let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = doy - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+	(year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn dates() {
+		assert_eq!(civil_from_days(0), (1970, 1, 1));
+		assert_eq!(civil_from_days(11016), (2000, 2, 29));
+		assert_eq!(civil_from_days(20742), (2026, 10, 16));
+	}
+
+	#[test]
+	fn placeholders() {
+		let lookup = |name: &str| Ok((name == "date").then(|| "20261016".to_owned()));
+		assert_eq!(expand("nightly.{date}", lookup).unwrap(), "nightly.20261016");
+		assert_eq!(expand("dev", lookup).unwrap(), "dev");
+		assert_eq!(expand("nightly.{sha}", lookup).unwrap(), "nightly.{sha}");
+		assert_eq!(expand("nightly.{date", lookup).unwrap(), "nightly.{date");
+		assert_eq!(expand("{{date}} {{{date}}}", lookup).unwrap(), "{date} {20261016}");
+		assert_eq!(expand("{ on = \"{date}\" }", lookup).unwrap(), "{ on = \"20261016\" }");
+	}
+}
@@ -10,7 +10,7 @@ fn argparse_1() {
 	let args = Args::try_parse_from(
 		"cargo-dragons set version 1.0.0 -p crateA -p crateB".split_ascii_whitespace(),
 	);
-	assert_matches!(args.unwrap().cmd, cli::Command::Set { pkg_opts, root_key: _, name, value } => {
+	assert_matches!(args.unwrap().cmd, cli::Command::Set { pkg_opts, root_key: _, name, value, .. } => {
 		assert_eq!(Vec::from_iter(pkg_opts.packages.into_iter().map(|x| x.to_string())), vec!["crateA", "crateB"]);
 		assert_eq!(name, "version");
 		assert_eq!(value, "1.0.0");
@@ -22,7 +22,7 @@ fn argparse_2() {
 	let args = Args::try_parse_from(
 		"cargo-dragons set -p crateA -p crateB version 1.0.0".split_ascii_whitespace(),
 	);
-	assert_matches!(args.unwrap().cmd, cli::Command::Set { pkg_opts, root_key: _, name, value } => {
+	assert_matches!(args.unwrap().cmd, cli::Command::Set { pkg_opts, root_key: _, name, value, .. } => {
 		assert_eq!(Vec::from_iter(pkg_opts.packages.into_iter().map(|x| x.to_string())), vec!["crateA", "crateB"]);
 		assert_eq!(name, "version");
 		assert_eq!(value, "1.0.0");
@@ -39,9 +39,84 @@ fn argparse_3() {
 		"authors",
 		"Bernhard Schuster <bernhard@ahoi.io>",
 	]);
-	assert_matches!(args.unwrap().cmd, cli::Command::Set { pkg_opts, root_key: _, name, value } => {
+	assert_matches!(args.unwrap().cmd, cli::Command::Set { pkg_opts, root_key: _, name, value, .. } => {
 		assert_eq!(Vec::from_iter(pkg_opts.packages.into_iter().map(|x| x.to_string())), vec!["crate0"]);
 		assert_eq!(name, "authors");
 		assert_eq!(value, "Bernhard Schuster <bernhard@ahoi.io>");
 	});
 }
+
+#[test]
+fn argparse_explain() {
+	let args = Args::try_parse_from(
+		"cargo-dragons set --explain version 1.0.0 -p crateA".split_ascii_whitespace(),
+	);
+	assert_matches!(args.unwrap().cmd, cli::Command::Set { pkg_opts, root_key: _, name, value, .. } => {
+		assert_eq!(pkg_opts.explain, Some(util::ReportFormat::Table));
+		assert_eq!(name, "version");
+		assert_eq!(value, "1.0.0");
+	});
+
+	let args = Args::try_parse_from(
+		"cargo-dragons to-release --explain=json --skip crateA".split_ascii_whitespace(),
+	);
+	assert_matches!(args.unwrap().cmd, cli::Command::ToRelease { pkg_opts, .. } => {
+		assert_eq!(pkg_opts.explain, Some(util::ReportFormat::Json));
+	});
+}
+
+#[test]
+fn argparse_config_defaults() {
+	let config: config::WorkspaceConfig = toml::from_str("include-dev-deps = true").unwrap();
+
+	let mut cmd = Args::try_parse_from("cargo-dragons to-release".split_ascii_whitespace())
+		.unwrap()
+		.cmd;
+	cmd.apply_config(&config).unwrap();
+	assert_matches!(cmd, cli::Command::ToRelease { include_dev: true, .. });
+
+	let mut cmd = Args::try_parse_from(
+		"cargo-dragons to-release --no-include-dev-deps".split_ascii_whitespace(),
+	)
+	.unwrap()
+	.cmd;
+	cmd.apply_config(&config).unwrap();
+	assert_matches!(cmd, cli::Command::ToRelease { include_dev: false, .. });
+
+	let args = Args::try_parse_from(
+		"cargo-dragons check --include-dev-deps --no-include-dev-deps".split_ascii_whitespace(),
+	);
+	assert!(args.is_err());
+}
+
+#[test]
+fn argparse_run_profile() {
+	let args = Args::try_parse_from(
+		"cargo-dragons run nightly --skip crateA --explain".split_ascii_whitespace(),
+	);
+	assert_matches!(args.unwrap().cmd, cli::Command::Run { profile, list, extra } => {
+		assert_eq!(profile.as_deref(), Some("nightly"));
+		assert!(!list);
+		assert_eq!(extra, vec!["--skip", "crateA", "--explain"]);
+	});
+
+	let args = Args::try_parse_from("cargo-dragons run --list".split_ascii_whitespace());
+	assert_matches!(args.unwrap().cmd, cli::Command::Run { profile: None, list: true, .. });
+
+	assert!(Args::try_parse_from("cargo-dragons run".split_ascii_whitespace()).is_err());
+}
+
+#[test]
+fn argparse_set_append() {
+	let args = Args::try_parse_from(vec![
+		"cargo-dragons",
+		"set",
+		"--append",
+		"package.keywords",
+		r#"["cargo", "release"]"#,
+	]);
+	assert_matches!(args.unwrap().cmd, cli::Command::Set { root_key: None, name, value, append: true, .. } => {
+		assert_eq!(name, "package.keywords");
+		assert_eq!(value, r#"["cargo", "release"]"#);
+	});
+}
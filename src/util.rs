@@ -6,10 +6,14 @@ use cargo::{
 };
 use git2::Repository;
 use log::{trace, warn};
-use std::{collections::HashSet, fs};
-use toml_edit::{DocumentMut, InlineTable, Item, Table, Value};
+use std::{collections::HashSet, path::PathBuf};
+use toml_edit::{DocumentMut, InlineTable, Item, Key, Table, Value};
 
-use crate::cli::PackageSelectOptions;
+use crate::{
+	cli::PackageSelectOptions,
+	config::{self, ChangeMode},
+	editor::ManifestEditor,
+};
 
 pub fn changed_packages(
 	gctx: &GlobalContext,
@@ -37,24 +41,49 @@ pub fn changed_packages(
 		.diff_tree_to_tree(Some(&current_head), Some(&main), None)
 		.context("Diffing failed")?;
 
-	let files = Vec::from_iter(
+	let files = HashSet::<PathBuf>::from_iter(
 		diff.deltas()
-			.filter_map(|d| d.new_file().path())
-			.filter_map(|d| if d.is_file() { d.parent() } else { Some(d) })
+			.flat_map(|d| [d.old_file().path(), d.new_file().path()])
+			.flatten()
 			.map(|l| path.join(l)),
 	);
 
 	trace!("Files changed since: {:#?}", files);
 
+	let ws_config = config::workspace_config(ws)?;
 	let mut packages = HashSet::new();
 
 	for m in members_deep(gctx, ws) {
 		let root = m.root();
-		for f in files.iter() {
-			if f.starts_with(root) {
-				packages.insert(m);
-				break;
+		let candidates = Vec::from_iter(files.iter().filter(|f| f.starts_with(root)));
+		if candidates.is_empty() {
+			continue;
+		}
+
+		let filter = ws_config.changes.merge(&config::package_config(&m)?.changes).filter()?;
+		let packaged = match filter.mode {
+			ChangeMode::Files => None,
+			ChangeMode::Packaged => {
+				let src = PathSource::new(root, m.package_id().source_id(), gctx);
+				Some(HashSet::<PathBuf>::from_iter(
+					src.list_files(&m)?.iter().map(|f| f.to_path_buf()),
+				))
+			},
+		};
+
+		let changed = candidates.into_iter().any(|f| {
+			if let Some(packaged) = &packaged {
+				// files deleted or renamed away can't be listed anymore, only the globs decide on
+				// them
+				if !packaged.contains(f) && f.exists() {
+					return false;
+				}
 			}
+			filter.is_relevant(f.strip_prefix(root).expect("Filtered by prefix before. qed"))
+		});
+		if changed {
+			trace!("{} changed since {}", m.name(), reference);
+			packages.insert(m);
 		}
 	}
 
@@ -84,8 +113,12 @@ pub fn members_deep(gctx: &GlobalContext, ws: &'_ Workspace) -> Vec<Package> {
 fn get_type_of<T>(_: &T) -> String {
 	std::any::type_name::<T>().to_owned()
 }
-/// Run f on every package's manifest, write the doc. Fail on first error
-pub fn edit_each<'a, I, F, R>(iter: I, f: F) -> Result<Vec<R>, anyhow::Error>
+/// Run f on every package's manifest, stage the doc in the editor. Fail on first error
+pub fn edit_each<'a, I, F, R>(
+	editor: &ManifestEditor,
+	iter: I,
+	f: F,
+) -> Result<Vec<R>, anyhow::Error>
 where
 	F: Fn(&'a Package, &mut DocumentMut) -> Result<R, anyhow::Error>,
 	I: Iterator<Item = &'a Package>,
@@ -93,20 +126,124 @@ where
 	let mut results = Vec::new();
 	for pkg in iter {
 		let manifest_path = pkg.manifest_path();
-		let content = fs::read_to_string(manifest_path)?;
+		let content = editor.read(manifest_path)?;
 		let mut doc: DocumentMut = content.parse()?;
 		results.push(f(pkg, &mut doc)?);
-		fs::write(manifest_path, doc.to_string())?;
+		let updated = doc.to_string();
+		if updated != content {
+			editor.write(manifest_path, updated)?;
+		}
 	}
 	Ok(results)
 }
 
+/// Run f on the workspace root manifest, stage the doc in the editor.
+pub fn edit_root<F, R>(
+	editor: &ManifestEditor,
+	ws: &Workspace<'_>,
+	f: F,
+) -> Result<R, anyhow::Error>
+where
+	F: FnOnce(&mut DocumentMut) -> Result<R, anyhow::Error>,
+{
+	let manifest_path = ws.root_manifest();
+	let content = editor.read(manifest_path)?;
+	let mut doc: DocumentMut = content.parse()?;
+	let result = f(&mut doc)?;
+	let updated = doc.to_string();
+	if updated != content {
+		editor.write(manifest_path, updated)?;
+	}
+	Ok(result)
+}
+
+/// The value without formatting, to compare values written differently
+pub fn normalized(value: &Value) -> String {
+	let mut value = value.clone();
+	value.decor_mut().clear();
+	match &mut value {
+		Value::Array(array) => array.fmt(),
+		Value::InlineTable(table) => table.fmt(),
+		_ => {},
+	}
+	value.to_string()
+}
+
+/// Tables and inline tables, which can both reorder their keys
+pub trait SortableTableKeysBy {
+	fn sort_values_by<F>(&mut self, compare: F)
+	where
+		F: FnMut(&Key, &Key) -> std::cmp::Ordering;
+}
+
+impl SortableTableKeysBy for Table {
+	fn sort_values_by<F>(&mut self, mut compare: F)
+	where
+		F: FnMut(&Key, &Key) -> std::cmp::Ordering,
+	{
+		Table::sort_values_by(self, move |k1, _, k2, _| compare(k1, k2));
+	}
+}
+
+impl SortableTableKeysBy for InlineTable {
+	fn sort_values_by<F>(&mut self, mut compare: F)
+	where
+		F: FnMut(&Key, &Key) -> std::cmp::Ordering,
+	{
+		InlineTable::sort_values_by(self, move |k1, _, k2, _| compare(k1, k2));
+	}
+}
+
+/// Move `workspace = true` to the front of a dependency entry, keeping the order of the others
+pub fn workspace_first<T: SortableTableKeysBy>(table: &mut T) {
+	table.sort_values_by(|key1, key2| match (key1.get(), key2.get()) {
+		("workspace", "workspace") => std::cmp::Ordering::Equal,
+		("workspace", _) => std::cmp::Ordering::Less,
+		(_, "workspace") => std::cmp::Ordering::Greater,
+		_ => std::cmp::Ordering::Equal,
+	});
+}
+
 /// Wrap each the different dependency as a mutable item
 pub enum DependencyEntry<'a> {
 	Table(&'a mut Table),
 	Inline(&'a mut InlineTable),
 }
 
+impl DependencyEntry<'_> {
+	/// The string value of `key`, if set
+	pub fn get_str(&self, key: &str) -> Option<&str> {
+		match self {
+			DependencyEntry::Inline(info) => info.get(key).and_then(|v| v.as_str()),
+			DependencyEntry::Table(info) => info.get(key).and_then(|v| v.as_str()),
+		}
+	}
+
+	pub fn contains_key(&self, key: &str) -> bool {
+		match self {
+			DependencyEntry::Inline(info) => info.contains_key(key),
+			DependencyEntry::Table(info) => info.contains_key(key),
+		}
+	}
+
+	/// Set the `version` requirement, adding it if missing
+	pub fn set_requirement(&mut self, requirement: String) {
+		let value = Value::from(requirement);
+		match self {
+			DependencyEntry::Inline(info) => match info.get_mut("version") {
+				Some(v_req) => *v_req = value.decorated(" ", ""),
+				None => {
+					info.insert("version", value);
+					info.fmt();
+				},
+			},
+			DependencyEntry::Table(info) => {
+				info["version"] = Item::Value(value.decorated(" ", ""));
+			},
+		}
+	}
+}
+
 #[derive(Debug, PartialEq, Eq)]
 /// The action (should be) taken on the dependency entry
 pub enum DependencyAction {
@@ -127,20 +264,177 @@ pub enum DependencySection {
 	Dev,
 	/// A build dependency
 	Build,
+	/// A `[workspace.dependencies]` entry of the root manifest
+	Workspace,
+	/// A section restricted to a platform, e.g. `[target.'cfg(unix)'.dev-dependencies]`
+	Target { cfg: String, section: Box<DependencySection> },
 }
 
 impl DependencySection {
-	fn key(&self) -> &'static str {
+	/// The section of a package's manifest stored under `key`
+	fn of(key: &str) -> Option<DependencySection> {
+		match key {
+			"dependencies" => Some(DependencySection::Regular),
+			"dev-dependencies" => Some(DependencySection::Dev),
+			"build-dependencies" => Some(DependencySection::Build),
+			_ => None,
+		}
+	}
+
+	/// The platform the section is restricted to, e.g. `cfg(unix)`
+	pub fn target(&self) -> Option<&str> {
 		match self {
-			DependencySection::Regular => "dependencies",
+			DependencySection::Target { cfg, .. } => Some(cfg),
+			_ => None,
+		}
+	}
+
+	/// The section without the platform restriction
+	pub fn base(&self) -> &DependencySection {
+		match self {
+			DependencySection::Target { section, .. } => section,
+			section => section,
+		}
+	}
+
+	pub fn is_dev(&self) -> bool {
+		*self.base() == DependencySection::Dev
+	}
+
+	/// The key of the section's table in the manifest
+	fn key(&self) -> &'static str {
+		match self.base() {
 			DependencySection::Dev => "dev-dependencies",
 			DependencySection::Build => "build-dependencies",
+			DependencySection::Workspace => "workspace.dependencies",
+			_ => "dependencies",
+		}
+	}
+}
+
+/// The table header of the section, e.g. `[target.'cfg(unix)'.dependencies]`
+impl std::fmt::Display for DependencySection {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.target() {
+			Some(cfg) => write!(f, "[target.'{}'.{}]", cfg, self.key()),
+			None => write!(f, "[{}]", self.key()),
+		}
+	}
+}
+
+/// All dependency tables of the package manifest `root`, including those restricted to a
+/// platform in `[target.'<cfg>']`
+pub fn dependency_tables(root: &mut Table) -> Vec<(DependencySection, &mut Table)> {
+	let mut tables = Vec::new();
+	for (key, item) in root.iter_mut() {
+		if key.get() == "target" {
+			let Some(targets) = item.as_table_mut() else { continue };
+			for (cfg, target) in targets.iter_mut() {
+				let Some(target) = target.as_table_mut() else { continue };
+				for (key, item) in target.iter_mut() {
+					if let (Some(section), Some(table)) =
+						(DependencySection::of(key.get()), item.as_table_mut())
+					{
+						let cfg = cfg.get().to_owned();
+						tables.push((
+							DependencySection::Target { cfg, section: Box::new(section) },
+							table,
+						));
+					}
+				}
+			}
+		} else if let (Some(section), Some(table)) =
+			(DependencySection::of(key.get()), item.as_table_mut())
+		{
+			tables.push((section, table));
+		}
+	}
+	tables
+}
+
+/// Hand each dependency entry of the dependency table `t`, that is a subsection, to f.
+/// Collect the names of removed entries in `removed`, return the counter of how many
+/// entries were changed.
+fn edit_dep_table<F>(
+	t: &mut Table,
+	section: DependencySection,
+	f: &F,
+	removed: &mut Vec<String>,
+) -> u32
+where
+	F: Fn(String, Option<String>, DependencyEntry, DependencySection) -> DependencyAction,
+{
+	let mut counter = 0;
+	let keys = Vec::from_iter(t.iter().filter_map(|(key, v)| {
+		if v.is_table() || v.is_inline_table() {
+			Some(key.to_owned())
+		} else {
+			None
+		}
+	}));
+	for key in keys {
+		let (name, action) = match t.get_mut(&key) {
+			Some(Item::Value(Value::InlineTable(info))) => {
+				let (name, alias) = info
+					.get("package")
+					.map(|name| {
+						(
+							name.as_str().expect("Package is always a valid UTF-8. qed").to_owned(),
+							Some(key.clone()),
+						)
+					})
+					.unwrap_or_else(|| (key.clone(), None));
+				(name.clone(), f(name, alias, DependencyEntry::Inline(info), section.clone()))
+			},
+			Some(Item::Table(info)) => {
+				let (name, alias) = info
+					.get("package")
+					.map(|name| {
+						(
+							name.as_str().expect("Package is always a valid UTF-8. qed").to_owned(),
+							Some(key.clone()),
+						)
+					})
+					.unwrap_or_else(|| (key.clone(), None));
+				(name.clone(), f(name, alias, DependencyEntry::Table(info), section.clone()))
+			},
+			None => continue,
+			info => {
+				warn!("Unsupported dependency format for {}. Format must be InlinedTable/Table, not {}", key, get_type_of(&info));
+				(key.clone(), DependencyAction::Untouched)
+			},
+		};
+
+		match action {
+			DependencyAction::Remove => {
+				t.remove(&name);
+				removed.push(name);
+			},
+			DependencyAction::Untouched => { /* nop */ },
+			_ => {
+				counter += 1;
+			},
 		}
 	}
+	counter
 }
 
-/// Iterate through the dependency sections of root, find each
-/// dependency entry, that is a subsection and hand it and its name
+/// Iterate through the `[workspace.dependencies]` of the root manifest, hand each
+/// dependency entry, that is a subsection, and its name to f. Return the counter of how
+/// many times f returned true.
+pub fn edit_workspace_deps<F>(root: &mut Table, f: F) -> u32
+where
+	F: Fn(String, Option<String>, DependencyEntry, DependencySection) -> DependencyAction,
+{
+	let mut removed = Vec::new();
+	match root.get_mut("workspace").and_then(|w| w.get_mut("dependencies")) {
+		Some(Item::Table(t)) => edit_dep_table(t, DependencySection::Workspace, &f, &mut removed),
+		_ => 0,
+	}
+}
+
+/// Iterate through the dependency sections of root, including the target specific ones, find
+/// each dependency entry, that is a subsection and hand it and its name
 /// to f. Return the counter of how many times f returned true.
 pub fn edit_each_dep<F>(root: &mut Table, f: F) -> u32
 where
@@ -148,65 +442,8 @@ where
 {
 	let mut counter = 0;
 	let mut removed = Vec::new();
-	for case in [DependencySection::Regular, DependencySection::Dev, DependencySection::Build] {
-		let k = case.key();
-		if let Some(Item::Table(t)) = root.get_mut(k) {
-			let keys = Vec::from_iter(t.iter().filter_map(|(key, v)| {
-				if v.is_table() || v.is_inline_table() {
-					Some(key.to_owned())
-				} else {
-					None
-				}
-			}));
-			for key in keys {
-				let (name, action) = match t.get_mut(&key) {
-					Some(Item::Value(Value::InlineTable(info))) => {
-						let (name, alias) = info
-							.get("package")
-							.map(|name| {
-								(
-									name.as_str()
-										.expect("Package is always a valid UTF-8. qed")
-										.to_owned(),
-									Some(key.clone()),
-								)
-							})
-							.unwrap_or_else(|| (key.clone(), None));
-						(name.clone(), f(name, alias, DependencyEntry::Inline(info), case.clone()))
-					},
-					Some(Item::Table(info)) => {
-						let (name, alias) = info
-							.get("package")
-							.map(|name| {
-								(
-									name.as_str()
-										.expect("Package is always a valid UTF-8. qed")
-										.to_owned(),
-									Some(key.clone()),
-								)
-							})
-							.unwrap_or_else(|| (key.clone(), None));
-						(name.clone(), f(name, alias, DependencyEntry::Table(info), case.clone()))
-					},
-					None => continue,
-					info => {
-						warn!("Unsupported dependency format for {}. Format must be InlinedTable/Table, not {}", key, get_type_of(&info));
-						(key.clone(), DependencyAction::Untouched)
-					},
-				};
-
-				match action {
-					DependencyAction::Remove => {
-						t.remove(&name);
-						removed.push(name);
-					},
-					DependencyAction::Untouched => { /* nop */ },
-					_ => {
-						counter += 1;
-					},
-				}
-			}
-		}
+	for (section, t) in dependency_tables(root) {
+		counter += edit_dep_table(t, section, &f, &mut removed);
 	}
 
 	if !removed.is_empty() {
@@ -256,7 +493,7 @@ pub(crate) fn empty_package_bool_to_action(empty_package_is_failure: bool) -> Em
 }
 
 pub(crate) fn handle_empty_package_is_failures<T>(
-	packages: &Vec<T>,
+	packages: &[T],
 	empty_package_is_failure: bool,
 ) -> anyhow::Result<()> {
 	if packages.is_empty() {
@@ -274,11 +511,168 @@ pub(crate) fn handle_empty_package_is_failures<T>(
 	Ok(())
 }
 
+/// Why a package was selected or skipped by the [`PackageSelectOptions`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SelectReason {
+	/// `publish` is set to `false` or restricted to other registries
+	PublishField,
+	/// `release = false` is set in `[package.metadata.dragons]`
+	ReleaseDisabled,
+	/// The name matches the given `-p/--packages` expression
+	Matched(String),
+	/// The name matches none of the `-p/--packages` expressions
+	NotMatched,
+	/// The name matches the given `-s/--skip` expression
+	Skipped(String),
+	/// The pre-release is listed in `-i/--ignore-pre-version`
+	PreReleaseIgnored(String),
+	/// Files changed since the given git reference
+	ChangedSince(String),
+	/// The sources differ from the published version
+	ChangedVsRegistry,
+	/// Neither changed since the git reference nor compared to the registry
+	Unchanged,
+	/// Has a pre-release and `--include-pre-deps` is set
+	PreDependency,
+	/// No criteria excluded it
+	Default,
+	/// Selected, but this version is already published
+	AlreadyPublished,
+}
+
+impl SelectReason {
+	pub(crate) fn is_selected(&self) -> bool {
+		match self {
+			SelectReason::Matched(_) |
+			SelectReason::ChangedSince(_) |
+			SelectReason::ChangedVsRegistry |
+			SelectReason::PreDependency |
+			SelectReason::Default => true,
+			SelectReason::PublishField |
+			SelectReason::ReleaseDisabled |
+			SelectReason::NotMatched |
+			SelectReason::Skipped(_) |
+			SelectReason::PreReleaseIgnored(_) |
+			SelectReason::Unchanged |
+			SelectReason::AlreadyPublished => false,
+		}
+	}
+}
+
+impl std::fmt::Display for SelectReason {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			SelectReason::PublishField => write!(f, "`publish` field excludes it"),
+			SelectReason::ReleaseDisabled => write!(f, "`release = false` configured"),
+			SelectReason::Matched(r) => write!(f, "matches `--packages {}`", r),
+			SelectReason::NotMatched => write!(f, "matches no `--packages`"),
+			SelectReason::Skipped(r) => write!(f, "matches `--skip {}`", r),
+			SelectReason::PreReleaseIgnored(pre) => write!(f, "pre-release `{}` is ignored", pre),
+			SelectReason::ChangedSince(r) => write!(f, "changed since `{}`", r),
+			SelectReason::ChangedVsRegistry => write!(f, "changed compared to published version"),
+			SelectReason::Unchanged => write!(f, "unchanged"),
+			SelectReason::PreDependency => write!(f, "pulled in by `--include-pre-deps`"),
+			SelectReason::Default => write!(f, "selected by default"),
+			SelectReason::AlreadyPublished => write!(f, "already published"),
+		}
+	}
+}
+
+/// How reports, like the explanation of the selection, are printed
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+	/// A human readable table
+	Table,
+	/// A JSON array, with one object per package
+	Json,
+}
+
+#[derive(serde::Serialize)]
+struct ExplainRow {
+	name: String,
+	version: String,
+	selected: bool,
+	reason: String,
+}
+
+/// The packages selected by the [`PackageSelectOptions`], with the reason of the decision
+pub(crate) struct PackageSelector {
+	explain: Option<ReportFormat>,
+	reason: Box<dyn Fn(&Package) -> SelectReason>,
+}
+
+impl PackageSelector {
+	pub(crate) fn reason(&self, p: &Package) -> SelectReason {
+		(self.reason)(p)
+	}
+
+	pub(crate) fn is_selected(&self, p: &Package) -> bool {
+		self.reason(p).is_selected()
+	}
+
+	/// Print the reason for every package of the workspace, if `--explain` was given.
+	///
+	/// Selected packages missing from `to_release` are reported as already published.
+	pub(crate) fn explain(
+		&self,
+		gctx: &GlobalContext,
+		ws: &Workspace<'_>,
+		to_release: Option<&[Package]>,
+	) -> Result<(), anyhow::Error> {
+		let Some(format) = self.explain else { return Ok(()) };
+
+		let mut seen = HashSet::new();
+		let rows = Vec::from_iter(members_deep(gctx, ws).into_iter().filter_map(|p| {
+			if !seen.insert(p.name()) {
+				return None;
+			}
+			let mut reason = self.reason(&p);
+			if let Some(to_release) = to_release {
+				if reason.is_selected() && !to_release.iter().any(|r| r.name() == p.name()) {
+					reason = SelectReason::AlreadyPublished;
+				}
+			}
+			Some(ExplainRow {
+				name: p.name().to_string(),
+				version: p.version().to_string(),
+				selected: reason.is_selected(),
+				reason: reason.to_string(),
+			})
+		}));
+
+		match format {
+			ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+			ReportFormat::Table => {
+				let name_width = rows.iter().map(|r| r.name.len()).max().unwrap_or_default();
+				let version_width = rows.iter().map(|r| r.version.len()).max().unwrap_or_default();
+				for ExplainRow { name, version, selected, reason } in rows {
+					let selected = if selected { "selected" } else { "skipped" };
+					println!(
+						"{name:name_width$}  {version:version_width$}  {selected:8}  {reason}"
+					);
+				}
+			},
+		}
+		Ok(())
+	}
+}
+
+/// Build the predicate selecting packages and print the explanation, if requested
 pub(crate) fn make_pkg_predicate(
 	gctx: &GlobalContext,
 	ws: &Workspace<'_>,
 	args: PackageSelectOptions,
 ) -> Result<impl Fn(&Package) -> bool, anyhow::Error> {
+	let selector = make_pkg_selector(gctx, ws, args)?;
+	selector.explain(gctx, ws, None)?;
+	Ok(move |p: &Package| selector.is_selected(p))
+}
+
+pub(crate) fn make_pkg_selector(
+	gctx: &GlobalContext,
+	ws: &Workspace<'_>,
+	args: PackageSelectOptions,
+) -> Result<PackageSelector, anyhow::Error> {
 	let PackageSelectOptions {
 		packages,
 		skip,
@@ -286,6 +680,9 @@ pub(crate) fn make_pkg_predicate(
 		ignore_publish,
 		changed_since,
 		include_pre_deps,
+		changed_vs_registry,
+		registry_cache,
+		explain,
 	} = args;
 
 	if !packages.is_empty() {
@@ -297,6 +694,9 @@ pub(crate) fn make_pkg_predicate(
 		if changed_since.is_some() {
 			anyhow::bail!("-p/--packages is mutually exclusive to using -c/--changed-since");
 		}
+		if changed_vs_registry {
+			anyhow::bail!("-p/--packages is mutually exclusive to using --changed-vs-registry");
+		}
 	}
 
 	let publish = move |p: &Package| {
@@ -309,47 +709,89 @@ pub(crate) fn make_pkg_predicate(
 	};
 	let check_version = move |p: &Package| include_pre_deps && !p.version().pre.is_empty();
 
+	let mut release_disabled = HashSet::new();
+	if !ignore_publish {
+		for p in members_deep(gctx, ws) {
+			if !config::package_config(&p)?.release() {
+				release_disabled.insert(p.name());
+			}
+		}
+	}
+
 	let changed = if let Some(changed_since) = &changed_since {
 		if !skip.is_empty() || !ignore_pre_version.is_empty() {
 			anyhow::bail!(
                 "-c/--changed-since is mutually exclusive to using -s/--skip and -i/--ignore-version-pre"
             );
 		}
-		Some(crate::util::changed_packages(&gctx, ws, changed_since)?)
+		Some(crate::util::changed_packages(gctx, ws, changed_since)?)
+	} else {
+		None
+	};
+
+	let changed_vs_registry = if changed_vs_registry {
+		if !skip.is_empty() || !ignore_pre_version.is_empty() {
+			anyhow::bail!(
+				"--changed-vs-registry is mutually exclusive to using -s/--skip and -i/--ignore-version-pre"
+			);
+		}
+		// packaging is expensive, skip the packages that wouldn't be selected anyways
+		Some(crate::registry::changed_vs_registry(gctx, ws, &registry_cache, publish)?)
 	} else {
 		None
 	};
 
-	Ok(move |p: &Package| {
+	let reason = move |p: &Package| {
 		if !publish(p) {
-			return false;
+			return SelectReason::PublishField;
+		}
+		if release_disabled.contains(&p.name()) {
+			return SelectReason::ReleaseDisabled;
 		}
 
-		if let Some(changed) = &changed {
-			return changed.contains(p) || check_version(p);
+		if changed.is_some() || changed_vs_registry.is_some() {
+			if let (Some(changed), Some(reference)) = (&changed, &changed_since) {
+				if changed.contains(p) {
+					return SelectReason::ChangedSince(reference.clone());
+				}
+			}
+			if let Some(changed) = &changed_vs_registry {
+				if changed.contains(p) {
+					return SelectReason::ChangedVsRegistry;
+				}
+			}
+			if check_version(p) {
+				return SelectReason::PreDependency;
+			}
+			return SelectReason::Unchanged;
 		}
 
 		if !packages.is_empty() {
 			trace!("going for matching against {:?}", packages);
 			let name = p.name();
-			if packages.iter().any(|r| r.is_match(&name)) {
-				return true;
+			if let Some(r) = packages.iter().find(|r| r.is_match(&name)) {
+				return SelectReason::Matched(r.to_string());
+			}
+			if check_version(p) {
+				return SelectReason::PreDependency;
 			}
-			return check_version(p);
+			return SelectReason::NotMatched;
 		}
 
 		if !skip.is_empty() || !ignore_pre_version.is_empty() {
 			let name = p.name();
-			if skip.iter().any(|r| r.is_match(&name)) {
-				return false;
+			if let Some(r) = skip.iter().find(|r| r.is_match(&name)) {
+				return SelectReason::Skipped(r.to_string());
 			}
 			if !p.version().pre.is_empty() &&
 				ignore_pre_version.contains(&p.version().pre.as_str().to_owned())
 			{
-				return false;
+				return SelectReason::PreReleaseIgnored(p.version().pre.to_string());
 			}
 		}
 
-		true
-	})
+		SelectReason::Default
+	};
+
+	Ok(PackageSelector { explain, reason: Box::new(reason) })
 }
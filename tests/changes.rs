@@ -0,0 +1,50 @@
+mod common;
+
+use assert_cmd::prelude::*;
+
+use common::{dragons, fixture, package};
+use git2::{IndexAddOption, Repository, Signature};
+use semver::Version;
+use std::path::Path;
+
+/// Stage all files of the work tree and commit them onto `HEAD`
+fn commit_all(repo: &Repository, message: &str) -> Result<(), git2::Error> {
+	let mut index = repo.index()?;
+	index.add_all(["*"], IndexAddOption::DEFAULT, None)?;
+	index.update_all(["*"], None)?;
+	index.write()?;
+	let tree = repo.find_tree(index.write_tree()?)?;
+	let signature = Signature::now("dragons", "dragons@example.com")?;
+	let parent = repo.head().ok().map(|h| h.peel_to_commit()).transpose()?;
+	repo.commit(
+		Some("HEAD"),
+		&signature,
+		&signature,
+		message,
+		&tree,
+		Vec::from_iter(&parent).as_slice(),
+	)?;
+	Ok(())
+}
+
+#[test]
+fn packaged_changes_include_deleted_files() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("changes")?;
+	let repo = Repository::init(temp.path())?;
+	commit_all(&repo, "Initial")?;
+	repo.branch("base", &repo.head()?.peel_to_commit()?, false)?;
+
+	std::fs::remove_file(temp.path().join(Path::new("a/src/old.rs")))?;
+	commit_all(&repo, "Remove a module")?;
+
+	dragons(&temp)
+		.args(["version", "bump-patch", "--changed-since", "base"])
+		.assert()
+		.success();
+
+	assert_eq!(package(&temp, "a")?.version(), &Version::parse("0.1.1")?);
+	assert_eq!(package(&temp, "b")?.version(), &Version::parse("0.1.0")?); // unchanged
+
+	temp.close()?;
+	Ok(())
+}
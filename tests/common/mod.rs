@@ -0,0 +1,31 @@
+#![allow(dead_code)]
+
+use assert_cmd::prelude::*;
+use assert_fs::{prelude::*, TempDir};
+use cargo::{
+	core::{Package, SourceId},
+	ops::read_package,
+	GlobalContext,
+};
+use std::{error::Error, path::Path, process::Command};
+
+/// Copy the workspace in `tests/fixtures/<name>` into a temporary directory
+pub fn fixture(name: &str) -> Result<TempDir, Box<dyn Error>> {
+	let temp = TempDir::new()?;
+	temp.copy_from(Path::new("tests/fixtures").join(name), &["*.toml", "*.rs"])?;
+	Ok(temp)
+}
+
+/// `cargo-dragons` operating on the workspace in `temp`
+pub fn dragons(temp: &TempDir) -> Command {
+	let mut cmd = Command::cargo_bin("cargo-dragons").expect("The binary is built for tests. qed");
+	cmd.arg("--manifest-path").arg(temp.path());
+	cmd
+}
+
+/// Read the package in `dir`, relative to the workspace in `temp`
+pub fn package(temp: &TempDir, dir: &str) -> Result<Package, Box<dyn Error>> {
+	let gctx = GlobalContext::default()?;
+	let source = SourceId::for_path(temp.path())?;
+	Ok(read_package(&temp.path().join(dir).join("Cargo.toml"), source, &gctx)?)
+}
@@ -0,0 +1,3 @@
+pub fn make() -> usize {
+	std::mem::size_of::<base::Thing>()
+}
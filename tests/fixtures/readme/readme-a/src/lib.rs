@@ -0,0 +1 @@
+//! Documentation of readme-a
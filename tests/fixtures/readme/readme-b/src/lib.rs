@@ -0,0 +1 @@
+//! Documentation of readme-b
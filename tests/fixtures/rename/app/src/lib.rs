@@ -0,0 +1,7 @@
+#[cfg(feature = "thing")]
+use old_name::Thing;
+
+#[cfg(feature = "thing")]
+pub fn thing() -> Thing {
+	old_name::make()
+}
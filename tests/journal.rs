@@ -0,0 +1,47 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::{dragons, fixture};
+
+#[test]
+fn undo_restores_manifests() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("simple-base")?;
+	let manifest = temp.path().join("crateA").join("Cargo.toml");
+	let original = std::fs::read_to_string(&manifest)?;
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["version", "set-pre", "dev", "--packages", "crateA"]);
+	cmd.assert().success();
+	assert_ne!(std::fs::read_to_string(&manifest)?, original);
+
+	let mut cmd = dragons(&temp);
+	cmd.arg("undo");
+	cmd.assert().success();
+	assert_eq!(std::fs::read_to_string(&manifest)?, original);
+
+	// nothing left to undo
+	let mut cmd = dragons(&temp);
+	cmd.arg("undo");
+	cmd.assert().failure();
+
+	temp.close()?;
+	Ok(())
+}
+
+#[test]
+fn dry_run_prints_diff() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("simple-base")?;
+	let manifest = temp.path().join("crateA").join("Cargo.toml");
+	let original = std::fs::read_to_string(&manifest)?;
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["version", "set-pre", "dev", "--packages", "crateA", "--dry-run"]);
+	cmd.assert()
+		.success()
+		.stdout(predicates::str::contains("+++ b/crateA/Cargo.toml"))
+		.stdout(predicates::str::contains("+version = \"0.1.0-dev\""));
+	assert_eq!(std::fs::read_to_string(&manifest)?, original);
+
+	temp.close()?;
+	Ok(())
+}
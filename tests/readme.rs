@@ -0,0 +1,25 @@
+#![cfg(feature = "gen-readme")]
+
+mod common;
+
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use common::{dragons, fixture};
+
+#[test]
+fn readme_mode_from_config() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("readme")?;
+	temp.child("readme-a/README.md").write_str("# Handwritten\n")?;
+	temp.child("readme-b/README.md").write_str("# Outdated\n")?;
+
+	dragons(&temp).arg("gen-readme").assert().success();
+
+	// the workspace default keeps existing files, `readme-b` overrides it
+	let readme_a = std::fs::read_to_string(temp.path().join("readme-a/README.md"))?;
+	let readme_b = std::fs::read_to_string(temp.path().join("readme-b/README.md"))?;
+	assert_eq!(readme_a, "# Handwritten\n");
+	assert!(readme_b.contains("Documentation of readme-b"), "{}", readme_b);
+
+	temp.close()?;
+	Ok(())
+}
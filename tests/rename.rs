@@ -0,0 +1,92 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::{dragons, fixture};
+
+#[test]
+fn rename_fully_with_directory() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("rename")?;
+	let read = |path: &str| std::fs::read_to_string(temp.path().join(path));
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["rename", "old-name", "new-name", "--full", "--move-dir"]);
+	cmd.assert().success();
+
+	assert!(!temp.path().join("crates/old-name").exists());
+	assert!(read("crates/new-name/Cargo.toml")?.contains(r#"name = "new-name""#));
+	let root = read("Cargo.toml")?;
+	assert!(root.contains(r#"members = ["app", "crates/new-name"]"#));
+	assert!(root.contains(r#"new-name = { path = "crates/new-name" }"#));
+	let app = read("app/Cargo.toml")?;
+	assert!(app.contains("new-name = { workspace = true, optional = true }"));
+	assert!(app.contains(r#"std = ["new-name?/std"]"#));
+	assert!(app.contains(r#"thing = ["dep:new-name"]"#));
+	assert!(!app.contains("package ="));
+	let source = read("app/src/lib.rs")?;
+	assert!(source.contains("use new_name::Thing;"));
+	assert!(source.contains("new_name::make()"));
+	assert!(!source.contains("old_name"));
+
+	// the move is undone together with the edits
+	let mut cmd = dragons(&temp);
+	cmd.arg("undo");
+	cmd.assert().success();
+	assert!(!temp.path().join("crates/new-name").exists());
+	assert!(read("crates/old-name/Cargo.toml")?.contains(r#"name = "old-name""#));
+	assert!(read("app/src/lib.rs")?.contains("old_name"));
+
+	temp.close()?;
+	Ok(())
+}
+
+#[test]
+fn rename_keeping_facade() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("rename")?;
+	let read = |path: &str| std::fs::read_to_string(temp.path().join(path));
+
+	// dry runs show the facade replacing the moved crate
+	let mut cmd = dragons(&temp);
+	cmd.args([
+		"--dry-run",
+		"rename",
+		"old-name",
+		"new-name",
+		"--full",
+		"--move-dir",
+		"--keep-facade",
+	]);
+	cmd.assert()
+		.success()
+		.stdout(predicates::str::contains("+pub use new_name::*;"));
+	assert!(!temp.path().join("crates/new-name").exists());
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["rename", "old-name", "new-name", "--full", "--keep-facade"]);
+	cmd.assert().success();
+
+	let facade = read("crates/old-name-facade/Cargo.toml")?;
+	assert!(facade.contains(r#"name = "old-name""#));
+	// inherited like by the renamed crate
+	assert!(facade.contains("version.workspace = true"));
+	assert!(facade.contains("edition.workspace = true"));
+	assert!(facade.contains("license.workspace = true"));
+	// in the configured `requirement-style`
+	assert!(facade.contains(r#"new-name = { version = "=0.1.0", path = "../old-name" }"#));
+	assert!(facade.contains(r#"std = ["new-name/std"]"#));
+	assert!(read("crates/old-name-facade/src/lib.rs")?.contains("pub use new_name::*;"));
+	assert!(read("crates/old-name-facade/README.md")?.contains("**Deprecated**"));
+	let root = read("Cargo.toml")?;
+	assert!(root.contains(r#""crates/old-name-facade""#));
+	assert!(root.contains(r#"old-name = ["new-name", "old-name"]"#));
+
+	// the facade follows the version of the renamed crate
+	let mut cmd = dragons(&temp);
+	cmd.args(["version", "bump-patch", "--packages", "^new-name$"]);
+	cmd.assert().success();
+	assert!(read("Cargo.toml")?.contains(r#"version = "0.1.1""#));
+	let facade = read("crates/old-name-facade/Cargo.toml")?;
+	assert!(facade.contains(r#"new-name = { version = "=0.1.1", path = "../old-name" }"#));
+
+	temp.close()?;
+	Ok(())
+}
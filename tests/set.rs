@@ -0,0 +1,73 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::{dragons, fixture};
+
+#[test]
+fn set_and_unset_fields() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("simple-base")?;
+	let manifest = temp.path().join("crateA").join("Cargo.toml");
+
+	let set = |args: &[&str]| {
+		let mut cmd = dragons(&temp);
+		cmd.args(args).args(["--packages", "crateA"]);
+		cmd.assert().success();
+	};
+	set(&["set", "package.metadata.docs.rs.all-features", "true"]);
+	set(&["set", "keywords", r#"["cargo"]"#]);
+	set(&["set", "--append", "keywords", r#"["cargo", "release"]"#]);
+
+	let content = std::fs::read_to_string(&manifest)?;
+	assert!(content.contains("[package.metadata.docs.rs]\nall-features = true"));
+	assert!(content.contains(r#"keywords = ["cargo", "release"]"#));
+
+	set(&["unset", "package.metadata.docs.rs"]);
+	set(&["unset", "keywords"]);
+	let content = std::fs::read_to_string(&manifest)?;
+	assert!(!content.contains("docs"));
+	assert!(!content.contains("keywords"));
+
+	temp.close()?;
+	Ok(())
+}
+
+#[test]
+fn set_with_package_placeholders() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("simple-base")?;
+
+	let mut cmd = dragons(&temp);
+	cmd.args([
+		"set",
+		"repository",
+		"https://example.com/tree/main/{path}?v={version}",
+		"--packages",
+		"crate(A|B)",
+	]);
+	cmd.assert().success();
+
+	let mut cmd = dragons(&temp);
+	cmd.args([
+		"set",
+		"package.metadata.dragons",
+		"{ docs = \"https://docs.rs/{name}\" }",
+		"--packages",
+		"crateA",
+	]);
+	cmd.assert().success();
+
+	let crate_a = std::fs::read_to_string(temp.path().join("crateA").join("Cargo.toml"))?;
+	let crate_b = std::fs::read_to_string(temp.path().join("crateB").join("Cargo.toml"))?;
+	assert!(crate_a.contains(r#"repository = "https://example.com/tree/main/crateA?v=0.1.0""#));
+	assert!(crate_b.contains(r#"repository = "https://example.com/tree/main/crateB?v=2.0.0""#));
+	assert!(crate_a.contains(r#"dragons = { docs = "https://docs.rs/crateA" }"#));
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["set", "documentation", "https://docs.rs/{nmae}/{{name}}"]);
+	cmd.assert().success();
+	// unknown placeholders are kept, `{{` and `}}` are literal braces
+	let crate_a = std::fs::read_to_string(temp.path().join("crateA").join("Cargo.toml"))?;
+	assert!(crate_a.contains(r#"documentation = "https://docs.rs/{nmae}/{name}""#));
+
+	temp.close()?;
+	Ok(())
+}
@@ -1,6 +1,9 @@
+mod common;
+
 use assert_cmd::prelude::*;
 use assert_fs::prelude::*;
-use cargo::{core::SourceId, ops::read_package, util::config::Config as CargoConfig};
+use cargo::{core::SourceId, ops::read_package, GlobalContext as CargoConfig};
+use common::{dragons, fixture, package};
 use semver::Version;
 use std::process::Command;
 
@@ -24,9 +27,9 @@ fn set_pre() -> Result<(), Box<dyn std::error::Error>> {
 	let temp_path = temp.path().to_path_buf();
 	let source = SourceId::for_path(temp.path())?;
 
-	let (crate_a, _) = read_package(&temp_path.join("crateA").join("Cargo.toml"), source, &cfg)?;
-	let (crate_b, _) = read_package(&temp_path.join("crateB").join("Cargo.toml"), source, &cfg)?;
-	let (crate_c, _) = read_package(&temp_path.join("crateC").join("Cargo.toml"), source, &cfg)?;
+	let crate_a = read_package(&temp_path.join("crateA").join("Cargo.toml"), source, &cfg)?;
+	let crate_b = read_package(&temp_path.join("crateB").join("Cargo.toml"), source, &cfg)?;
+	let crate_c = read_package(&temp_path.join("crateC").join("Cargo.toml"), source, &cfg)?;
 	assert_eq!(crate_a.version(), &Version::parse("0.1.0-dev")?);
 	assert_eq!(crate_b.version(), &Version::parse("2.0.0-dev")?);
 	assert_eq!(crate_c.version(), &Version::parse("3.1.0")?); // wasn't selected
@@ -54,9 +57,9 @@ fn bump_to_dev() -> Result<(), Box<dyn std::error::Error>> {
 	let temp_path = temp.path().to_path_buf();
 	let source = SourceId::for_path(temp.path())?;
 
-	let (crate_a, _) = read_package(&temp_path.join("crateA").join("Cargo.toml"), source, &cfg)?;
-	let (crate_b, _) = read_package(&temp_path.join("crateB").join("Cargo.toml"), source, &cfg)?;
-	let (crate_c, _) = read_package(&temp_path.join("crateC").join("Cargo.toml"), source, &cfg)?;
+	let crate_a = read_package(&temp_path.join("crateA").join("Cargo.toml"), source, &cfg)?;
+	let crate_b = read_package(&temp_path.join("crateB").join("Cargo.toml"), source, &cfg)?;
+	let crate_c = read_package(&temp_path.join("crateC").join("Cargo.toml"), source, &cfg)?;
 	assert_eq!(crate_a.version(), &Version::parse("0.2.0-dev")?);
 	assert_eq!(crate_b.version(), &Version::parse("3.0.0-dev")?);
 	assert_eq!(crate_c.version(), &Version::parse("4.0.0-dev")?);
@@ -64,3 +67,206 @@ fn bump_to_dev() -> Result<(), Box<dyn std::error::Error>> {
 	temp.close()?;
 	Ok(())
 }
+
+#[test]
+fn version_group_lockstep() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("version-groups")?;
+
+	dragons(&temp)
+		.args(["version", "bump-patch", "--packages", "^framework$"])
+		.assert()
+		.success();
+
+	// the maximum of the bumped versions of the group members
+	assert_eq!(package(&temp, "framework")?.version(), &Version::parse("0.1.4")?);
+	assert_eq!(package(&temp, "framework-macros")?.version(), &Version::parse("0.1.4")?);
+	assert_eq!(package(&temp, "other")?.version(), &Version::parse("1.0.0")?); // not part of the group
+
+	temp.close()?;
+	Ok(())
+}
+
+#[test]
+fn workspace_version_inheritance() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("workspace-version")?;
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["version", "bump-minor", "--packages", "^a$"]);
+	cmd.assert().success();
+
+	let crate_a = package(&temp, "a")?;
+	let crate_b = package(&temp, "b")?;
+	let crate_c = package(&temp, "c")?;
+	// both inherit the bumped workspace version
+	assert_eq!(crate_a.version(), &Version::parse("0.4.0")?);
+	assert_eq!(crate_b.version(), &Version::parse("0.4.0")?);
+	assert_eq!(crate_c.version(), &Version::parse("1.0.0")?);
+
+	let manifest = std::fs::read_to_string(temp.path().join("a").join("Cargo.toml"))?;
+	assert!(manifest.contains("version.workspace = true"));
+	let manifest = std::fs::read_to_string(temp.path().join("c").join("Cargo.toml"))?;
+	// the requirement keeps its precision
+	assert!(manifest.contains(r#"b = { version = "0.4", path = "../b" }"#));
+	let manifest = std::fs::read_to_string(temp.path().join("Cargo.toml"))?;
+	assert!(manifest.contains(r#"version = "0.4.0""#));
+	assert!(manifest.contains(r#"a = { version = "0.4.0", path = "a" }"#));
+
+	temp.close()?;
+	Ok(())
+}
+
+#[test]
+fn workspace_version_mixed_selection_fails() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("workspace-version")?;
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["version", "bump-patch", "--packages", "^(a|c)$"]);
+	cmd.assert().failure();
+
+	temp.close()?;
+	Ok(())
+}
+
+#[test]
+fn cascade_through_dependents() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("cascade")?;
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["version", "bump-breaking", "--cascade", "--packages", "^base$"]);
+	// the plan can't be confirmed without a terminal
+	cmd.stdin(std::process::Stdio::null());
+	cmd.assert().failure();
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["version", "bump-breaking", "--cascade", "--yes", "--packages", "^base$"]);
+	cmd.assert().success();
+
+	let version = |name: &str| -> Result<Version, Box<dyn std::error::Error>> {
+		Ok(package(&temp, name)?.version().clone())
+	};
+
+	assert_eq!(version("base")?, Version::parse("0.3.0")?);
+	// re-exports `base`, so it is breaking too
+	assert_eq!(version("api")?, Version::parse("2.0.0")?);
+	// only depending on bumped crates
+	assert_eq!(version("app")?, Version::parse("0.5.2")?);
+	assert_eq!(version("tool")?, Version::parse("2.0.1")?);
+	// dev-dependencies don't cascade
+	assert_eq!(version("dev-only")?, Version::parse("1.0.0")?);
+	// patch bumps of 0.0.x are breaking and cascade further
+	assert_eq!(version("nascent")?, Version::parse("0.0.4")?);
+	assert_eq!(version("on-nascent")?, Version::parse("1.0.1")?);
+
+	let manifest = std::fs::read_to_string(temp.path().join("app").join("Cargo.toml"))?;
+	assert!(manifest.contains(r#"api = { version = "2.0.0", path = "../api" }"#));
+
+	temp.close()?;
+	Ok(())
+}
+
+#[test]
+fn version_lint_and_fix() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("lint")?;
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["version", "lint"]);
+	cmd.assert().failure();
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["version", "lint", "--fix"]);
+	cmd.assert().success();
+
+	let manifest = std::fs::read_to_string(temp.path().join("b").join("Cargo.toml"))?;
+	assert!(manifest.contains(r#"a = { version = "0.2", path = "../a" }"#));
+	// dev-dependencies without a version are only warned about
+	assert!(manifest.contains(r#"a = { path = "../a" }"#));
+
+	// nothing left to fix
+	let mut cmd = dragons(&temp);
+	cmd.args(["version", "lint"]);
+	cmd.assert().success();
+
+	temp.close()?;
+	Ok(())
+}
+
+#[test]
+fn normalize_reqs_and_keep_style() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("cascade")?;
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["version", "normalize-reqs", "--style", "exact"]);
+	cmd.assert().success();
+
+	let manifest = std::fs::read_to_string(temp.path().join("tool").join("Cargo.toml"))?;
+	assert!(manifest.contains(r#"base = { version = "=0.2.0", path = "../base" }"#));
+	// missing requirements are left alone
+	let manifest = std::fs::read_to_string(temp.path().join("dev-only").join("Cargo.toml"))?;
+	assert!(manifest.contains(r#"base = { path = "../base" }"#));
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["version", "bump-patch", "--packages", "^base$"]);
+	cmd.assert().success();
+
+	// the exact requirement stays exact
+	let manifest = std::fs::read_to_string(temp.path().join("api").join("Cargo.toml"))?;
+	assert!(manifest.contains(r#"base = { version = "=0.2.1", path = "../base" }"#));
+
+	temp.close()?;
+	Ok(())
+}
+
+#[test]
+fn nightly_dated_pre_release() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("simple-base")?;
+
+	let mut cmd = dragons(&temp);
+	cmd.env("SOURCE_DATE_EPOCH", "1792108800")
+		.args(["version", "nightly", "--packages", "crateA"]);
+	cmd.assert().success();
+
+	// running again only replaces the pre-release
+	let mut cmd = dragons(&temp);
+	cmd.env("SOURCE_DATE_EPOCH", "1792195200")
+		.args(["version", "nightly", "--packages", "crateA"]);
+	cmd.assert().success();
+
+	let crate_a = package(&temp, "crateA")?;
+	assert_eq!(crate_a.version(), &Version::parse("0.2.0-nightly.20261017")?);
+
+	temp.close()?;
+	Ok(())
+}
+
+#[test]
+fn target_specific_dependencies() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("targets")?;
+
+	// the lint names the platform specific section of the mismatching requirement
+	let base = temp.path().join("base").join("Cargo.toml");
+	std::fs::write(&base, std::fs::read_to_string(&base)?.replace("0.1.0", "0.2.0"))?;
+	let mut cmd = dragons(&temp);
+	cmd.args(["version", "lint"]);
+	cmd.assert().failure().stderr(predicates::str::contains(
+		"app [target.'cfg(unix)'.dependencies]: requires base ^0.1.0, but the member is at 0.2.0",
+	));
+	std::fs::write(&base, std::fs::read_to_string(&base)?.replace("0.2.0", "0.1.0"))?;
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["version", "bump-minor", "--packages", "base"]);
+	cmd.assert().success();
+
+	let manifest = std::fs::read_to_string(temp.path().join("app").join("Cargo.toml"))?;
+	assert_eq!(manifest.matches(r#"base = { version = "0.2.0", path = "../base" }"#).count(), 2);
+
+	let mut cmd = dragons(&temp);
+	cmd.arg("de-dev-deps");
+	cmd.assert().success();
+
+	let manifest = std::fs::read_to_string(temp.path().join("app").join("Cargo.toml"))?;
+	assert!(manifest.contains("[target.'cfg(unix)'.dependencies]"));
+	assert!(!manifest.contains("dev-dependencies"));
+
+	temp.close()?;
+	Ok(())
+}
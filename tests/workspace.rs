@@ -0,0 +1,112 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::{dragons, fixture};
+
+#[test]
+fn inherit_shared_fields() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("inherit")?;
+	let read = |name: &str| std::fs::read_to_string(temp.path().join(name).join("Cargo.toml"));
+
+	let mut cmd = dragons(&temp);
+	cmd.arg("inherit");
+	cmd.assert().success();
+
+	let root = read(".")?;
+	assert!(root.contains("[workspace.package]"));
+	assert!(root.contains(r#"license = "Apache-2.0""#));
+	assert!(root.contains(r#"authors = ["Parity Technologies <admin@parity.io>"]"#));
+	// the members don't agree on the edition
+	assert!(!root.contains("edition"));
+	for member in ["a", "b", "c"] {
+		let manifest = read(member)?;
+		assert!(manifest.contains("license.workspace = true"));
+		assert!(manifest.contains("authors.workspace = true"));
+		assert!(!manifest.contains("edition.workspace = true"));
+	}
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["inherit", "--majority", "--field", "edition"]);
+	cmd.assert().success();
+	assert!(read(".")?.contains(r#"edition = "2021""#));
+	assert!(read("a")?.contains("edition.workspace = true"));
+	assert!(read("c")?.contains(r#"edition = "2018""#));
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["inherit", "--reverse", "--field", "license"]);
+	cmd.assert().success();
+	assert!(!read(".")?.contains("license"));
+	assert!(read("b")?.contains(r#"license = "Apache-2.0""#));
+
+	// paths are relative to the manifest setting them
+	let mut cmd = dragons(&temp);
+	cmd.args(["inherit", "--field", "readme"]);
+	cmd.assert().failure();
+
+	temp.close()?;
+	Ok(())
+}
+
+#[test]
+fn unify_lints_into_workspace() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("lints")?;
+	let read = |name: &str| std::fs::read_to_string(temp.path().join(name).join("Cargo.toml"));
+
+	let mut cmd = dragons(&temp);
+	cmd.arg("unify-lints");
+	cmd.assert().success();
+
+	let root = read(".")?;
+	assert!(root.contains("[workspace.lints.clippy]"));
+	assert!(root.contains(r#"unwrap_used = "deny""#));
+	// only set by one of the members
+	assert!(!root.contains("unsafe_code"));
+	let a = read("a")?;
+	assert!(a.contains("workspace = true"));
+	assert!(!a.contains("unwrap_used"));
+	assert!(read("b")?.contains(r#"unsafe_code = "forbid""#));
+	assert!(read("c")?.contains(r#"unwrap_used = "warn""#));
+
+	let mut cmd = dragons(&temp);
+	cmd.args(["unify-lints", "--check"]);
+	cmd.assert().failure();
+
+	let mut cmd = dragons(&temp);
+	cmd.args([
+		"unify-lints",
+		"--check",
+		"--allow",
+		"clippy::unwrap_used",
+		"--allow",
+		"rust::unsafe_code",
+	]);
+	cmd.assert().success();
+
+	temp.close()?;
+	Ok(())
+}
+
+#[test]
+fn fmt_manifests() -> Result<(), Box<dyn std::error::Error>> {
+	let temp = fixture("fmt")?;
+	let fmt = |check: bool| {
+		let mut cmd = dragons(&temp);
+		cmd.arg("fmt");
+		if check {
+			cmd.arg("--check");
+		}
+		cmd.assert()
+	};
+
+	fmt(true).failure();
+	fmt(false).success();
+	fmt(true).success();
+
+	let manifest = std::fs::read_to_string(temp.path().join("a").join("Cargo.toml"))?;
+	assert!(manifest.find("[package]") < manifest.find("[dependencies]"));
+	assert!(manifest.find("log") < manifest.find("serde"));
+	assert!(manifest.contains(r#"serde = { version = "1.0", default-features = false }"#));
+
+	temp.close()?;
+	Ok(())
+}